@@ -27,92 +27,428 @@ use {
     },
 };
 
-fn read_fields(internal: bool, sync: bool, fields: &Fields) -> proc_macro2::TokenStream {
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Endian {
+    Big,
+    Little,
+}
+
+impl Parse for Endian {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        Ok(match &*ident.to_string() {
+            "big" => Self::Big,
+            "little" => Self::Little,
+            _ => return Err(Error::new(ident.span(), "unknown endianness, expected `big` or `little`")),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum LenRepr {
+    U8,
+    U16,
+    U32,
+    U64,
+    Varint,
+}
+
+impl Parse for LenRepr {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        Ok(match &*ident.to_string() {
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "varint" => Self::Varint,
+            _ => return Err(Error::new(ident.span(), "unknown length representation, expected `u8`, `u16`, `u32`, `u64`, or `varint`")),
+        })
+    }
+}
+
+/// Returns the name of the given type if it is syntactically one of the primitive number types.
+fn primitive_number(ty: &Type) -> Option<String> {
+    if let Type::Path(TypePath { qself: None, path }) = ty {
+        path.get_ident().map(|ident| ident.to_string()).filter(|name| matches!(&**name, "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "u128" | "i128" | "f32" | "f64"))
+    } else {
+        None
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    bits: Option<u32>,
+    canonical: bool,
+    endian: Option<Endian>,
+    len: Option<LenRepr>,
+    max_len: Option<u64>,
+    varint: bool,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[Attribute]) -> std::result::Result<Self, proc_macro2::TokenStream> {
+        let mut field_attrs = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("async_proto")) {
+            match attr.parse_args_with(Punctuated::<FieldAttr, Token![,]>::parse_terminated) {
+                Ok(attrs) => for attr in attrs {
+                    match attr {
+                        FieldAttr::Bits(bits) => if field_attrs.bits.replace(bits).is_some() {
+                            return Err(quote!(compile_error!("#[async_proto(bits = ...)] specified multiple times");))
+                        },
+                        FieldAttr::Canonical => if field_attrs.canonical {
+                            return Err(quote!(compile_error!("#[async_proto(canonical)] specified multiple times");))
+                        } else {
+                            field_attrs.canonical = true;
+                        },
+                        FieldAttr::Endian(endian) => if field_attrs.endian.replace(endian).is_some() {
+                            return Err(quote!(compile_error!("#[async_proto(endian = ...)] specified multiple times");))
+                        },
+                        FieldAttr::Len(len) => if field_attrs.len.replace(len).is_some() {
+                            return Err(quote!(compile_error!("#[async_proto(len = ...)] specified multiple times");))
+                        },
+                        FieldAttr::MaxLen(max_len) => if field_attrs.max_len.replace(max_len).is_some() {
+                            return Err(quote!(compile_error!("#[async_proto(max_len = ...)] specified multiple times");))
+                        },
+                        FieldAttr::Varint => if field_attrs.varint {
+                            return Err(quote!(compile_error!("#[async_proto(varint)] specified multiple times");))
+                        } else {
+                            field_attrs.varint = true;
+                        },
+                    }
+                },
+                Err(e) => return Err(e.to_compile_error()),
+            }
+        }
+        if let Some(bits) = field_attrs.bits {
+            if !(1..=128).contains(&bits) {
+                return Err(quote!(compile_error!("#[async_proto(bits = ...)] must be between 1 and 128");))
+            }
+            if field_attrs.canonical || field_attrs.endian.is_some() || field_attrs.len.is_some() || field_attrs.max_len.is_some() || field_attrs.varint {
+                return Err(quote!(compile_error!("#[async_proto(bits = ...)] can't be combined with other field attributes");))
+            }
+        }
+        if field_attrs.len.is_some() && field_attrs.varint {
+            return Err(quote!(compile_error!("#[async_proto(len = ...)] and #[async_proto(varint)] are incompatible, use #[async_proto(len = varint)] instead");))
+        }
+        if field_attrs.endian.is_some() && field_attrs.varint {
+            return Err(quote!(compile_error!("#[async_proto(endian = ...)] and #[async_proto(varint)] are incompatible");))
+        }
+        Ok(field_attrs)
+    }
+
+    /// Determines how a field of the given type with these attributes is read/written.
+    fn repr(&self, async_proto_crate: &proc_macro2::TokenStream, ty: &Type, type_endian: Endian) -> std::result::Result<FieldRepr, proc_macro2::TokenStream> {
+        let primitive = primitive_number(ty);
+        Ok(if self.canonical {
+            if primitive.is_some() {
+                return Err(quote_spanned! {ty.span()=> compile_error!("#[async_proto(canonical)] can't be used on number fields"); })
+            }
+            FieldRepr::Canonical(self.length_prefix(async_proto_crate, type_endian))
+        } else if self.varint && primitive.is_some() {
+            match primitive.as_deref() {
+                Some("u8" | "u16" | "u32" | "u64" | "u128") => if self.max_len.is_some() {
+                    return Err(quote_spanned! {ty.span()=> compile_error!("#[async_proto(max_len = ...)] can't be used on integer fields"); })
+                } else {
+                    FieldRepr::Wrapper(quote!(#async_proto_crate::Varint))
+                },
+                Some("i8" | "i16" | "i32" | "i64" | "i128") => if self.max_len.is_some() {
+                    return Err(quote_spanned! {ty.span()=> compile_error!("#[async_proto(max_len = ...)] can't be used on integer fields"); })
+                } else {
+                    FieldRepr::Wrapper(quote!(#async_proto_crate::ZigZag))
+                },
+                _ => return Err(quote_spanned! {ty.span()=> compile_error!("#[async_proto(varint)] can't be used on floating-point fields"); }),
+            }
+        } else if self.max_len.is_some() || self.len.is_some() || self.varint {
+            FieldRepr::LengthPrefixed(self.length_prefix(async_proto_crate, type_endian))
+        } else if let Some(endian) = self.endian.or_else(|| (type_endian == Endian::Little && primitive.is_some_and(|name| !matches!(&*name, "u8" | "i8"))).then_some(type_endian)) {
+            FieldRepr::Wrapper(match endian {
+                Endian::Big => quote!(#async_proto_crate::Be),
+                Endian::Little => quote!(#async_proto_crate::Le),
+            })
+        } else {
+            FieldRepr::Protocol
+        })
+    }
+
+    /// Returns a `LengthPrefix` expression for the `endian`, `len`, `max_len`, and `varint` attributes, falling back to the given type-level byte order.
+    fn length_prefix(&self, async_proto_crate: &proc_macro2::TokenStream, type_endian: Endian) -> proc_macro2::TokenStream {
+        let max_len = self.max_len.unwrap_or(u64::MAX);
+        let repr = match self.len {
+            None if self.varint => quote!(Varint),
+            None => quote!(Auto),
+            Some(LenRepr::U8) => quote!(U8),
+            Some(LenRepr::U16) => quote!(U16),
+            Some(LenRepr::U32) => quote!(U32),
+            Some(LenRepr::U64) => quote!(U64),
+            Some(LenRepr::Varint) => quote!(Varint),
+        };
+        let endian = match self.endian.unwrap_or(type_endian) {
+            Endian::Big => quote!(Big),
+            Endian::Little => quote!(Little),
+        };
+        quote!(#async_proto_crate::LengthPrefix { max_len: #max_len, repr: #async_proto_crate::LengthRepr::#repr, endian: #async_proto_crate::Endian::#endian })
+    }
+}
+
+enum FieldRepr {
+    /// Read/written using the type's own `Protocol` impl.
+    Protocol,
+    /// Read/written via the `Protocol` impl of the given wrapper type, e.g. `Le`.
+    Wrapper(proc_macro2::TokenStream),
+    /// Read/written via the `LengthPrefixed` trait, with the given `LengthPrefix` expression.
+    LengthPrefixed(proc_macro2::TokenStream),
+    /// Read/written via the `CanonicalEncoding` trait, with the given `LengthPrefix` expression.
+    Canonical(proc_macro2::TokenStream),
+}
+
+#[derive(Clone, Copy)]
+struct TypeAttrs {
+    bitpacked: bool,
+    endian: Endian,
+    presence_bitmap: bool,
+}
+
+fn read_field(async_proto_crate: &proc_macro2::TokenStream, sync: bool, type_attrs: TypeAttrs, field_attrs: &FieldAttrs, ty: &Type) -> std::result::Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
+    Ok(match field_attrs.repr(async_proto_crate, ty, type_attrs.endian)? {
+        FieldRepr::Protocol => {
+            let read = if sync { quote!(::read_sync(stream)) } else { quote!(::read(stream).await) };
+            quote_spanned! {ty.span()=>
+                <#ty as #async_proto_crate::Protocol>#read
+            }
+        }
+        FieldRepr::Wrapper(wrapper) => {
+            let read = if sync { quote!(::read_sync(stream)) } else { quote!(::read(stream).await) };
+            quote_spanned! {ty.span()=>
+                <#wrapper<#ty> as #async_proto_crate::Protocol>#read.map(|#wrapper(value)| value)
+            }
+        }
+        FieldRepr::LengthPrefixed(prefix) => {
+            let read = if sync { quote!(::read_length_prefixed_sync(stream, #prefix)) } else { quote!(::read_length_prefixed(stream, #prefix).await) };
+            quote_spanned! {ty.span()=>
+                <#ty as #async_proto_crate::LengthPrefixed>#read
+            }
+        }
+        FieldRepr::Canonical(prefix) => {
+            let read = if sync { quote!(::read_canonical_sync(stream, #prefix)) } else { quote!(::read_canonical(stream, #prefix).await) };
+            quote_spanned! {ty.span()=>
+                <#ty as #async_proto_crate::CanonicalEncoding>#read
+            }
+        }
+    })
+}
+
+fn write_field(async_proto_crate: &proc_macro2::TokenStream, sync: bool, type_attrs: TypeAttrs, field_attrs: &FieldAttrs, ident: &Ident, ty: &Type) -> std::result::Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
+    Ok(match field_attrs.repr(async_proto_crate, ty, type_attrs.endian)? {
+        FieldRepr::Protocol => {
+            let write = if sync { quote!(::write_sync(#ident, sink)) } else { quote!(::write(#ident, sink).await) };
+            quote_spanned! {ty.span()=>
+                <#ty as #async_proto_crate::Protocol>#write
+            }
+        }
+        FieldRepr::Wrapper(wrapper) => {
+            let write = if sync { quote!(::write_sync(&#wrapper(*#ident), sink)) } else { quote!(::write(&#wrapper(*#ident), sink).await) };
+            quote_spanned! {ty.span()=>
+                <#wrapper<#ty> as #async_proto_crate::Protocol>#write
+            }
+        }
+        FieldRepr::LengthPrefixed(prefix) => {
+            let write = if sync { quote!(::write_length_prefixed_sync(#ident, sink, #prefix)) } else { quote!(::write_length_prefixed(#ident, sink, #prefix).await) };
+            quote_spanned! {ty.span()=>
+                <#ty as #async_proto_crate::LengthPrefixed>#write
+            }
+        }
+        FieldRepr::Canonical(prefix) => {
+            let write = if sync { quote!(::write_canonical_sync(#ident, sink, #prefix)) } else { quote!(::write_canonical(#ident, sink, #prefix).await) };
+            quote_spanned! {ty.span()=>
+                <#ty as #async_proto_crate::CanonicalEncoding>#write
+            }
+        }
+    })
+}
+
+/// Returns the type `T` if the given type is syntactically `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    if let Type::Path(TypePath { qself: None, path }) = ty {
+        let segment = path.segments.last()?;
+        if segment.ident != "Option" { return None }
+        if let PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) = &segment.arguments
+            && let Ok(GenericArgument::Type(inner)) = args.iter().exactly_one()
+        {
+            return Some(inner)
+        }
+    }
+    None
+}
+
+struct FieldInfo<'a> {
+    idx: usize,
+    field: &'a Field,
+    attrs: FieldAttrs,
+    /// If this field's presence is tracked in the presence bitmap, the type of the value inside the `Option`.
+    optional: Option<&'a Type>,
+}
+
+impl FieldInfo<'_> {
+    /// The identifier the field is bound to by the pattern returned from [`fields_pat`].
+    fn binding(&self) -> Ident {
+        self.field.ident.clone().unwrap_or_else(|| Ident::new(&format!("__field{}", self.idx), Span::call_site()))
+    }
+
+    /// Wraps the given error context in the context for this field.
+    fn context(&self, async_proto_crate: &proc_macro2::TokenStream, source: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        if let Some(ident) = &self.field.ident {
+            let name = ident.to_string();
+            quote! {
+                #async_proto_crate::ErrorContext::NamedField {
+                    name: #name,
+                    source: Box::new(#source),
+                }
+            }
+        } else {
+            let idx = self.idx;
+            quote! {
+                #async_proto_crate::ErrorContext::UnnamedField {
+                    idx: #idx,
+                    source: Box::new(#source),
+                }
+            }
+        }
+    }
+}
+
+/// Either a run of consecutive bit fields or a single other field.
+type FieldGroup<'a> = Vec<FieldInfo<'a>>;
+
+/// Parses the attributes of the given fields and splits them into runs of consecutive bit fields and individual other fields.
+///
+/// Also returns the number of fields tracked in the presence bitmap.
+fn field_groups(type_attrs: TypeAttrs, fields: &Fields) -> std::result::Result<(Vec<FieldGroup<'_>>, usize), proc_macro2::TokenStream> {
+    let mut groups = Vec::<Vec<_>>::default();
+    let mut num_optional = 0;
+    let mut in_bit_run = false;
+    for (idx, field) in fields.iter().enumerate() {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        let optional = if type_attrs.presence_bitmap { option_inner(&field.ty) } else { None };
+        if optional.is_some() {
+            if attrs.bits.is_some() {
+                return Err(quote_spanned! {field.span()=> compile_error!("#[async_proto(bits = ...)] can't be used on fields tracked in the presence bitmap"); })
+            }
+            num_optional += 1;
+        }
+        let is_bit_field = attrs.bits.is_some();
+        let info = FieldInfo { idx, field, attrs, optional };
+        if is_bit_field {
+            if !type_attrs.bitpacked {
+                return Err(quote_spanned! {field.span()=> compile_error!("#[async_proto(bits = ...)] requires #[async_proto(bitpacked)] on the type"); })
+            }
+            if in_bit_run {
+                groups.last_mut().expect("bit run without group").push(info);
+            } else {
+                groups.push(vec![info]);
+            }
+            in_bit_run = true;
+        } else {
+            groups.push(vec![info]);
+            in_bit_run = false;
+        }
+    }
+    Ok((groups, num_optional))
+}
+
+/// Returns an expression which reads the given fields and constructs a value using the given path (e.g. `Self` or `Self::Variant`).
+fn read_fields(internal: bool, sync: bool, type_attrs: TypeAttrs, ctor: proc_macro2::TokenStream, fields: &Fields) -> proc_macro2::TokenStream {
     let async_proto_crate = if internal { quote!(crate) } else { quote!(::async_proto) };
+    let (groups, num_optional) = match field_groups(type_attrs, fields) {
+        Ok(groups) => groups,
+        Err(e) => return e,
+    };
     let read = if sync { quote!(::read_sync(stream)) } else { quote!(::read(stream).await) };
-    match fields {
-        Fields::Unit => quote!(),
-        Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
-            let read_fields = unnamed.iter()
-                .enumerate()
-                .map(|(idx, Field { attrs, ty, .. })| {
-                    let mut max_len = None;
-                    for attr in attrs.into_iter().filter(|attr| attr.path().is_ident("async_proto")) {
-                        match attr.parse_args_with(Punctuated::<FieldAttr, Token![,]>::parse_terminated) {
-                            Ok(attrs) => for attr in attrs {
-                                match attr {
-                                    FieldAttr::MaxLen(new_max_len) => if max_len.replace(new_max_len).is_some() {
-                                        return quote!(compile_error!("#[async_proto(max_len = ...)] specified multiple times");).into()
-                                    },
-                                }
-                            },
-                            Err(e) => return e.to_compile_error().into(),
-                        }
-                    }
-                    let read = if let Some(max_len) = max_len {
-                        let read = if sync { quote!(::read_length_prefixed_sync(stream, #max_len)) } else { quote!(::read_length_prefixed(stream, #max_len).await) };
-                        quote_spanned! {ty.span()=>
-                            <#ty as #async_proto_crate::LengthPrefixed>#read
-                        }
-                    } else {
-                        quote_spanned! {ty.span()=>
-                            <#ty as #async_proto_crate::Protocol>#read
-                        }
-                    };
-                    quote_spanned! {ty.span()=>
-                        #read.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
-                            context: #async_proto_crate::ErrorContext::UnnamedField {
-                                idx: #idx,
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })?
+    let mut read_stmts = Vec::default();
+    if num_optional > 0 {
+        let num_bytes = num_optional.div_ceil(8);
+        read_stmts.push(quote! {
+            let __presence = <[u8; #num_bytes] as #async_proto_crate::Protocol>#read.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
+                context: #async_proto_crate::ErrorContext::PresenceBitmap {
+                    source: Box::new(context),
+                },
+                kind,
+            })?;
+            let mut __presence_reader = #async_proto_crate::BitReader::new(&__presence);
+        });
+    }
+    for group in groups {
+        if let Some(total_bits) = group.iter().map(|info| info.attrs.bits).sum::<Option<u32>>() {
+            let num_bytes = total_bits.div_ceil(8) as usize;
+            let context = group[0].context(&async_proto_crate, quote!(context));
+            read_stmts.push(quote! {
+                let __bits = <[u8; #num_bytes] as #async_proto_crate::Protocol>#read.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
+                    context: #context,
+                    kind,
+                })?;
+                let mut __bit_reader = #async_proto_crate::BitReader::new(&__bits);
+            });
+            for info in &group {
+                let bits = info.attrs.bits.expect("bit run with non-bit field");
+                let ty = &info.field.ty;
+                let var = Ident::new(&format!("__field{}", info.idx), Span::call_site());
+                let context = info.context(&async_proto_crate, quote!(#async_proto_crate::ErrorContext::BitField));
+                read_stmts.push(quote_spanned! {ty.span()=>
+                    let #var = <#ty as #async_proto_crate::BitField>::from_bits(__bit_reader.read(#bits), #bits).map_err(|kind| #async_proto_crate::ReadError {
+                        context: #context,
+                        kind,
+                    })?;
+                });
+            }
+        } else {
+            for info in &group {
+                let ty = info.optional.unwrap_or(&info.field.ty);
+                let var = Ident::new(&format!("__field{}", info.idx), Span::call_site());
+                let read = match read_field(&async_proto_crate, sync, type_attrs, &info.attrs, ty) {
+                    Ok(read) => read,
+                    Err(e) => return e,
+                };
+                let context = info.context(&async_proto_crate, quote!(context));
+                let read = quote_spanned! {ty.span()=>
+                    #read.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
+                        context: #context,
+                        kind,
+                    })?
+                };
+                read_stmts.push(if info.optional.is_some() {
+                    quote! {
+                        let #var = if __presence_reader.read(1) != 0 {
+                            ::core::option::Option::Some(#read)
+                        } else {
+                            ::core::option::Option::None
+                        };
                     }
-                })
-                .collect_vec();
-            quote!((#(#read_fields,)*))
+                } else {
+                    quote!(let #var = #read;)
+                });
+            }
+        }
+    }
+    let value = match fields {
+        Fields::Unit => ctor,
+        Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
+            let vars = (0..unnamed.len()).map(|idx| Ident::new(&format!("__field{}", idx), Span::call_site()));
+            quote!(#ctor(#(#vars,)*))
         }
         Fields::Named(FieldsNamed { named, .. }) => {
-            let read_fields = named.iter()
-                .map(|Field { attrs, ident, ty, .. }| {
-                    let mut max_len = None;
-                    for attr in attrs.into_iter().filter(|attr| attr.path().is_ident("async_proto")) {
-                        match attr.parse_args_with(Punctuated::<FieldAttr, Token![,]>::parse_terminated) {
-                            Ok(attrs) => for attr in attrs {
-                                match attr {
-                                    FieldAttr::MaxLen(new_max_len) => if max_len.replace(new_max_len).is_some() {
-                                        return quote!(compile_error!("#[async_proto(max_len = ...)] specified multiple times");).into()
-                                    },
-                                }
-                            },
-                            Err(e) => return e.to_compile_error().into(),
-                        }
-                    }
-                    let name = ident.as_ref().expect("FieldsNamed with unnamed field").to_string();
-                    let read = if let Some(max_len) = max_len {
-                        let read = if sync { quote!(::read_length_prefixed_sync(stream, #max_len)) } else { quote!(::read_length_prefixed(stream, #max_len).await) };
-                        quote_spanned! {ty.span()=>
-                            <#ty as #async_proto_crate::LengthPrefixed>#read
-                        }
-                    } else {
-                        quote_spanned! {ty.span()=>
-                            <#ty as #async_proto_crate::Protocol>#read
-                        }
-                    };
-                    quote_spanned! {ty.span()=>
-                        #ident: #read.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
-                            context: #async_proto_crate::ErrorContext::NamedField {
-                                name: #name,
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })?
-                    }
-                })
-                .collect_vec();
-            quote!({ #(#read_fields,)* })
+            let field_inits = named.iter()
+                .enumerate()
+                .map(|(idx, Field { ident, .. })| {
+                    let var = Ident::new(&format!("__field{}", idx), Span::call_site());
+                    quote!(#ident: #var)
+                });
+            quote!(#ctor { #(#field_inits,)* })
         }
-    }
+    };
+    quote!({
+        #(#read_stmts)*
+        #value
+    })
 }
 
 fn fields_pat(fields: &Fields) -> proc_macro2::TokenStream {
@@ -134,96 +470,104 @@ fn fields_pat(fields: &Fields) -> proc_macro2::TokenStream {
     }
 }
 
-fn write_fields(internal: bool, sync: bool, fields: &Fields) -> proc_macro2::TokenStream {
+/// Returns statements which write the given fields, which must be bound using the pattern returned from [`fields_pat`].
+fn write_fields(internal: bool, sync: bool, type_attrs: TypeAttrs, fields: &Fields) -> proc_macro2::TokenStream {
     let async_proto_crate = if internal { quote!(crate) } else { quote!(::async_proto) };
-    match fields {
-        Fields::Unit => quote!(),
-        Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
-            let write_fields = unnamed.iter()
-                .enumerate()
-                .map(|(idx, Field { attrs, ty, .. })| {
-                    let mut max_len = None;
-                    for attr in attrs.into_iter().filter(|attr| attr.path().is_ident("async_proto")) {
-                        match attr.parse_args_with(Punctuated::<FieldAttr, Token![,]>::parse_terminated) {
-                            Ok(attrs) => for attr in attrs {
-                                match attr {
-                                    FieldAttr::MaxLen(new_max_len) => if max_len.replace(new_max_len).is_some() {
-                                        return quote!(compile_error!("#[async_proto(max_len = ...)] specified multiple times");).into()
-                                    },
-                                }
-                            },
-                            Err(e) => return e.to_compile_error().into(),
-                        }
-                    }
-                    let ident = Ident::new(&format!("__field{}", idx), Span::call_site());
-                    let write = if let Some(max_len) = max_len {
-                        let write = if sync { quote!(::write_length_prefixed_sync(#ident, sink, #max_len)) } else { quote!(::write_length_prefixed(#ident, sink, #max_len).await) };
-                        quote_spanned! {ty.span()=>
-                            <#ty as #async_proto_crate::LengthPrefixed>#write
-                        }
-                    } else {
-                        let write = if sync { quote!(::write_sync(#ident, sink)) } else { quote!(::write(#ident, sink).await) };
-                        quote_spanned! {ty.span()=>
-                            <#ty as #async_proto_crate::Protocol>#write
-                        }
-                    };
-                    quote!(#write.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
-                        context: #async_proto_crate::ErrorContext::UnnamedField {
-                            idx: #idx,
-                            source: Box::new(context),
-                        },
-                        kind,
-                    })?;)
+    let (groups, num_optional) = match field_groups(type_attrs, fields) {
+        Ok(groups) => groups,
+        Err(e) => return e,
+    };
+    let mut write_stmts = Vec::default();
+    if num_optional > 0 {
+        let num_bytes = num_optional.div_ceil(8);
+        let write_presence = groups.iter()
+            .flatten()
+            .filter(|info| info.optional.is_some())
+            .map(|info| {
+                let var = info.binding();
+                quote!(__presence_writer.write(::core::option::Option::is_some(#var).into(), 1);)
+            });
+        let write = if sync { quote!(::write_sync(&__presence_writer.into_bytes(), sink)) } else { quote!(::write(&__presence_writer.into_bytes(), sink).await) };
+        write_stmts.push(quote! {
+            let mut __presence_writer = #async_proto_crate::BitWriter::<#num_bytes>::default();
+            #(#write_presence)*
+            <[u8; #num_bytes] as #async_proto_crate::Protocol>#write.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
+                context: #async_proto_crate::ErrorContext::PresenceBitmap {
+                    source: Box::new(context),
+                },
+                kind,
+            })?;
+        });
+    }
+    for group in groups {
+        if let Some(total_bits) = group.iter().map(|info| info.attrs.bits).sum::<Option<u32>>() {
+            let num_bytes = total_bits.div_ceil(8) as usize;
+            write_stmts.push(quote!(let mut __bit_writer = #async_proto_crate::BitWriter::<#num_bytes>::default();));
+            for info in &group {
+                let bits = info.attrs.bits.expect("bit run with non-bit field");
+                let ty = &info.field.ty;
+                let var = info.binding();
+                let context = info.context(&async_proto_crate, quote!(#async_proto_crate::ErrorContext::BitField));
+                write_stmts.push(quote_spanned! {ty.span()=>
+                    __bit_writer.write(<#ty as #async_proto_crate::BitField>::to_bits(#var, #bits).ok_or_else(|| #async_proto_crate::WriteError {
+                        context: #context,
+                        kind: #async_proto_crate::WriteErrorKind::BitFieldOverflow { bits: #bits },
+                    })?, #bits);
                 });
-            quote!(#(#write_fields)*)
-        }
-        Fields::Named(FieldsNamed { named, .. }) => {
-            let write_fields = named.iter()
-                .map(|Field { attrs, ident, ty, .. }| {
-                    let mut max_len = None;
-                    for attr in attrs.into_iter().filter(|attr| attr.path().is_ident("async_proto")) {
-                        match attr.parse_args_with(Punctuated::<FieldAttr, Token![,]>::parse_terminated) {
-                            Ok(attrs) => for attr in attrs {
-                                match attr {
-                                    FieldAttr::MaxLen(new_max_len) => if max_len.replace(new_max_len).is_some() {
-                                        return quote!(compile_error!("#[async_proto(max_len = ...)] specified multiple times");).into()
-                                    },
-                                }
-                            },
-                            Err(e) => return e.to_compile_error().into(),
-                        }
-                    }
-                    let write = if let Some(max_len) = max_len {
-                        let write = if sync { quote!(::write_length_prefixed_sync(#ident, sink, #max_len)) } else { quote!(::write_length_prefixed(#ident, sink, #max_len).await) };
-                        quote_spanned! {ty.span()=>
-                            <#ty as #async_proto_crate::LengthPrefixed>#write
-                        }
-                    } else {
-                        let write = if sync { quote!(::write_sync(#ident, sink)) } else { quote!(::write(#ident, sink).await) };
-                        quote_spanned! {ty.span()=>
-                            <#ty as #async_proto_crate::Protocol>#write
+            }
+            let context = group[0].context(&async_proto_crate, quote!(context));
+            let write = if sync { quote!(::write_sync(&__bit_writer.into_bytes(), sink)) } else { quote!(::write(&__bit_writer.into_bytes(), sink).await) };
+            write_stmts.push(quote! {
+                <[u8; #num_bytes] as #async_proto_crate::Protocol>#write.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
+                    context: #context,
+                    kind,
+                })?;
+            });
+        } else {
+            for info in &group {
+                let var = info.binding();
+                let context = info.context(&async_proto_crate, quote!(context));
+                if let Some(ty) = info.optional {
+                    let value = Ident::new("__value", Span::call_site());
+                    let write = match write_field(&async_proto_crate, sync, type_attrs, &info.attrs, &value, ty) {
+                        Ok(write) => write,
+                        Err(e) => return e,
+                    };
+                    write_stmts.push(quote! {
+                        if let ::core::option::Option::Some(#value) = #var {
+                            #write.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
+                                context: #context,
+                                kind,
+                            })?;
                         }
+                    });
+                } else {
+                    let write = match write_field(&async_proto_crate, sync, type_attrs, &info.attrs, &var, &info.field.ty) {
+                        Ok(write) => write,
+                        Err(e) => return e,
                     };
-                    let name = ident.as_ref().expect("FieldsNamed with unnamed field").to_string();
-                    quote!(#write.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
-                        context: #async_proto_crate::ErrorContext::NamedField {
-                            name: #name,
-                            source: Box::new(context),
-                        },
-                        kind,
-                    })?;)
-                });
-            quote!(#(#write_fields)*)
+                    write_stmts.push(quote! {
+                        #write.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
+                            context: #context,
+                            kind,
+                        })?;
+                    });
+                }
+            }
         }
     }
+    quote!(#(#write_stmts)*)
 }
 
 enum AsyncProtoAttr {
     AsString,
     Attr(Punctuated<Meta, Token![,]>),
+    Bitpacked,
     Clone,
+    Endian(Endian),
     Internal,
     MapErr(Expr),
+    PresenceBitmap,
     Via(Type),
     Where(Punctuated<WherePredicate, Token![,]>),
 }
@@ -244,12 +588,18 @@ impl Parse for AsyncProtoAttr {
                     parenthesized!(content in input);
                     Self::Attr(Punctuated::parse_terminated(&content)?)
                 }
+                "bitpacked" => Self::Bitpacked,
                 "clone" => Self::Clone,
+                "endian" => {
+                    let _ = input.parse::<Token![=]>()?;
+                    Self::Endian(input.parse()?)
+                }
                 "internal" => Self::Internal,
                 "map_err" => {
                     let _ = input.parse::<Token![=]>()?;
                     Self::MapErr(input.parse()?)
                 }
+                "presence_bitmap" => Self::PresenceBitmap,
                 "via" => {
                     let _ = input.parse::<Token![=]>()?;
                     Self::Via(input.parse()?)
@@ -261,17 +611,36 @@ impl Parse for AsyncProtoAttr {
 }
 
 enum FieldAttr {
+    Bits(u32),
+    Canonical,
+    Endian(Endian),
+    Len(LenRepr),
     MaxLen(u64),
+    Varint,
 }
 
 impl Parse for FieldAttr {
     fn parse(input: ParseStream<'_>) -> Result<Self> {
         let ident = input.parse::<Ident>()?;
         Ok(match &*ident.to_string() {
+            "bits" => {
+                let _ = input.parse::<Token![=]>()?;
+                Self::Bits(input.parse::<LitInt>()?.base10_parse()?)
+            }
+            "canonical" => Self::Canonical,
+            "endian" => {
+                let _ = input.parse::<Token![=]>()?;
+                Self::Endian(input.parse()?)
+            }
+            "len" => {
+                let _ = input.parse::<Token![=]>()?;
+                Self::Len(input.parse()?)
+            }
             "max_len" => {
                 let _ = input.parse::<Token![=]>()?;
                 Self::MaxLen(input.parse::<LitInt>()?.base10_parse()?)
             }
+            "varint" => Self::Varint,
             _ => return Err(Error::new(ident.span(), "unknown async_proto field attribute")),
         })
     }
@@ -281,7 +650,10 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
     let for_type = quote!(#qual_ty).to_string();
     let mut as_string = false;
     let mut via = None;
+    let mut bitpacked = false;
+    let mut presence_bitmap = false;
     let mut clone = false;
+    let mut endian = None;
     let mut map_err = None;
     let mut where_predicates = None;
     let mut impl_attrs = Vec::default();
@@ -290,24 +662,29 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
             Ok(attrs) => for attr in attrs {
                 match attr {
                     AsyncProtoAttr::AsString => {
-                        if via.is_some() { return quote!(compile_error!("#[async_proto(as_str)] and #[async_proto(via = ...)] are incompatible");).into() }
+                        if via.is_some() { return quote!(compile_error!("#[async_proto(as_str)] and #[async_proto(via = ...)] are incompatible");) }
                         as_string = true;
                     }
                     AsyncProtoAttr::Attr(attr) => impl_attrs.extend(attr),
+                    AsyncProtoAttr::Bitpacked => bitpacked = true,
                     AsyncProtoAttr::Clone => clone = true,
+                    AsyncProtoAttr::Endian(new_endian) => if endian.replace(new_endian).is_some() {
+                        return quote!(compile_error!("#[async_proto(endian = ...)] specified multiple times");)
+                    },
                     AsyncProtoAttr::Internal => internal = true,
                     AsyncProtoAttr::MapErr(expr) => if map_err.replace(expr).is_some() {
-                        return quote!(compile_error!("#[async_proto(map_err = ...)] specified multiple times");).into()
+                        return quote!(compile_error!("#[async_proto(map_err = ...)] specified multiple times");)
                     },
+                    AsyncProtoAttr::PresenceBitmap => presence_bitmap = true,
                     AsyncProtoAttr::Via(ty) => if via.replace(ty).is_some() {
-                        return quote!(compile_error!("#[async_proto(via = ...)] specified multiple times");).into()
+                        return quote!(compile_error!("#[async_proto(via = ...)] specified multiple times");)
                     },
                     AsyncProtoAttr::Where(predicates) => if where_predicates.replace(predicates).is_some() {
-                        return quote!(compile_error!("#[async_proto(where(...))] specified multiple times");).into()
+                        return quote!(compile_error!("#[async_proto(where(...))] specified multiple times");)
                     },
                 }
             },
-            Err(e) => return e.to_compile_error().into(),
+            Err(e) => return e.to_compile_error(),
         }
     }
     let async_proto_crate = if internal { quote!(crate) } else { quote!(::async_proto) };
@@ -323,8 +700,12 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
             param.bounds.push(parse_quote!('static));
         }
     };
+    if endian.is_some() && (as_string || via.is_some()) { return quote!(compile_error!("#[async_proto(endian = ...)] does nothing with #[async_proto(as_string)] or #[async_proto(via = ...)]");) }
+    if bitpacked && (as_string || via.is_some()) { return quote!(compile_error!("#[async_proto(bitpacked)] does nothing with #[async_proto(as_string)] or #[async_proto(via = ...)]");) }
+    if presence_bitmap && (as_string || via.is_some()) { return quote!(compile_error!("#[async_proto(presence_bitmap)] does nothing with #[async_proto(as_string)] or #[async_proto(via = ...)]");) }
+    let mut impl_bit_field = None;
     let (impl_read, impl_write, impl_read_sync, impl_write_sync) = if as_string {
-        if internal && data.is_some() { return quote!(compile_error!("redundant type layout specification with #[async_proto(as_string)]");).into() }
+        if internal && data.is_some() { return quote!(compile_error!("redundant type layout specification with #[async_proto(as_string)]");) }
         let map_err = map_err.unwrap_or(parse_quote!(::core::convert::Into::<#async_proto_crate::ReadErrorKind>::into));
         (
             quote!(<Self as ::std::str::FromStr>::from_str(&<::std::string::String as #async_proto_crate::Protocol>::read(stream).await.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
@@ -359,7 +740,7 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
             })),
         )
     } else if let Some(proxy_ty) = via {
-        if internal && data.is_some() { return quote!(compile_error!("redundant type layout specification with #[async_proto(via = ...)]");).into() }
+        if internal && data.is_some() { return quote!(compile_error!("redundant type layout specification with #[async_proto(via = ...)]");) }
         let (write_proxy, write_sync_proxy) = if clone {
             (
                 quote!(<Self as ::core::convert::TryInto<#proxy_ty>>::try_into(<Self as ::core::clone::Clone>::clone(self)).map_err(|e| #async_proto_crate::WriteError {
@@ -417,22 +798,24 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
             })),
         )
     } else {
-        if map_err.is_some() { return quote!(compile_error!("#[async_proto(map_err = ...)] does nothing without #[async_proto(as_string)] or #[async_proto(via = ...)]");).into() }
+        if map_err.is_some() { return quote!(compile_error!("#[async_proto(map_err = ...)] does nothing without #[async_proto(as_string)] or #[async_proto(via = ...)]");) }
+        let endian = endian.unwrap_or(Endian::Big);
+        let type_attrs = TypeAttrs { bitpacked, endian, presence_bitmap };
         match data {
             Some(Data::Struct(DataStruct { fields, .. })) => {
                 let fields_pat = fields_pat(&fields);
-                let read_fields_async = read_fields(internal, false, &fields);
-                let write_fields_async = write_fields(internal, false, &fields);
-                let read_fields_sync = read_fields(internal, true, &fields);
-                let write_fields_sync = write_fields(internal, true, &fields);
+                let read_fields_async = read_fields(internal, false, type_attrs, quote!(Self), &fields);
+                let write_fields_async = write_fields(internal, false, type_attrs, &fields);
+                let read_fields_sync = read_fields(internal, true, type_attrs, quote!(Self), &fields);
+                let write_fields_sync = write_fields(internal, true, type_attrs, &fields);
                 (
-                    quote!(::core::result::Result::Ok(Self #read_fields_async)),
+                    quote!(::core::result::Result::Ok(#read_fields_async)),
                     quote! {
                         let Self #fields_pat = self;
                         #write_fields_async
                         ::core::result::Result::Ok(())
                     },
-                    quote!(::core::result::Result::Ok(Self #read_fields_sync)),
+                    quote!(::core::result::Result::Ok(#read_fields_sync)),
                     quote! {
                         let Self #fields_pat = self;
                         #write_fields_sync
@@ -481,23 +864,67 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
                             quote!(#idx)
                         }) as &dyn Fn(usize) -> proc_macro2::TokenStream),
                     };
+                    let (read_discrim, read_sync_discrim, write_discrim, write_sync_discrim) = match endian {
+                        Endian::Big => (
+                            quote!(<#discrim_ty as #async_proto_crate::Protocol>::read(stream).await),
+                            quote!(<#discrim_ty as #async_proto_crate::Protocol>::read_sync(stream)),
+                            (&|idx: &proc_macro2::TokenStream| quote!(<#discrim_ty as #async_proto_crate::Protocol>::write(&#idx, sink).await)) as &dyn Fn(&proc_macro2::TokenStream) -> proc_macro2::TokenStream,
+                            (&|idx: &proc_macro2::TokenStream| quote!(<#discrim_ty as #async_proto_crate::Protocol>::write_sync(&#idx, sink))) as &dyn Fn(&proc_macro2::TokenStream) -> proc_macro2::TokenStream,
+                        ),
+                        Endian::Little => (
+                            quote!(<#async_proto_crate::Le<#discrim_ty> as #async_proto_crate::Protocol>::read(stream).await.map(|#async_proto_crate::Le(discrim)| discrim)),
+                            quote!(<#async_proto_crate::Le<#discrim_ty> as #async_proto_crate::Protocol>::read_sync(stream).map(|#async_proto_crate::Le(discrim)| discrim)),
+                            (&|idx: &proc_macro2::TokenStream| quote!(<#async_proto_crate::Le<#discrim_ty> as #async_proto_crate::Protocol>::write(&#async_proto_crate::Le(#idx), sink).await)) as &dyn Fn(&proc_macro2::TokenStream) -> proc_macro2::TokenStream,
+                            (&|idx: &proc_macro2::TokenStream| quote!(<#async_proto_crate::Le<#discrim_ty> as #async_proto_crate::Protocol>::write_sync(&#async_proto_crate::Le(#idx), sink))) as &dyn Fn(&proc_macro2::TokenStream) -> proc_macro2::TokenStream,
+                        ),
+                    };
+                    if variants.iter().all(|Variant { fields, .. }| matches!(fields, Fields::Unit)) {
+                        let to_bits_arms = variants.iter()
+                            .enumerate()
+                            .map(|(idx, Variant { ident: var, .. })| {
+                                let idx = idx as u128;
+                                quote!(Self::#var => #idx)
+                            });
+                        let from_bits_arms = variants.iter()
+                            .enumerate()
+                            .map(|(idx, Variant { ident: var, .. })| {
+                                let idx = idx as u128;
+                                quote!(#idx => ::core::result::Result::Ok(Self::#var))
+                            });
+                        impl_bit_field = Some(quote! {
+                            fn to_bits(&self, bits: u32) -> ::core::option::Option<u128> {
+                                let value = match self {
+                                    #(#to_bits_arms,)*
+                                };
+                                (bits >= u128::BITS || value >> bits == 0).then_some(value)
+                            }
+
+                            fn from_bits(value: u128, _: u32) -> ::core::result::Result<Self, #async_proto_crate::ReadErrorKind> {
+                                match value {
+                                    #(#from_bits_arms,)*
+                                    n => ::core::result::Result::Err(#async_proto_crate::ReadErrorKind::UnknownVariant128(n)),
+                                }
+                            }
+                        });
+                    }
                     let read_arms = variants.iter()
                         .enumerate()
                         .map(|(idx, Variant { ident: var, fields, .. })| {
                             let idx = get_discrim(idx);
-                            let read_fields = read_fields(internal, false, fields);
-                            quote!(#idx => ::core::result::Result::Ok(Self::#var #read_fields))
+                            let read_fields = read_fields(internal, false, type_attrs, quote!(Self::#var), fields);
+                            quote!(#idx => ::core::result::Result::Ok(#read_fields))
                         })
                         .collect_vec();
                     let write_arms = variants.iter()
                         .enumerate()
                         .map(|(idx, Variant { ident: var, fields, .. })| {
                             let idx = get_discrim(idx);
-                            let fields_pat = fields_pat(&fields);
-                            let write_fields = write_fields(internal, false, fields);
+                            let fields_pat = fields_pat(fields);
+                            let write_fields = write_fields(internal, false, type_attrs, fields);
+                            let write_discrim = write_discrim(&idx);
                             quote! {
                                 Self::#var #fields_pat => {
-                                    #idx.write(sink).await.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
+                                    #write_discrim.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
                                         context: #async_proto_crate::ErrorContext::EnumDiscrim {
                                             source: Box::new(context),
                                         },
@@ -512,19 +939,20 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
                         .enumerate()
                         .map(|(idx, Variant { ident: var, fields, .. })| {
                             let idx = get_discrim(idx);
-                            let read_fields = read_fields(internal, true, fields);
-                            quote!(#idx => ::core::result::Result::Ok(Self::#var #read_fields))
+                            let read_fields = read_fields(internal, true, type_attrs, quote!(Self::#var), fields);
+                            quote!(#idx => ::core::result::Result::Ok(#read_fields))
                         })
                         .collect_vec();
                     let write_sync_arms = variants.iter()
                         .enumerate()
                         .map(|(idx, Variant { ident: var, fields, .. })| {
                             let idx = get_discrim(idx);
-                            let fields_pat = fields_pat(&fields);
-                            let write_fields = write_fields(internal, true, fields);
+                            let fields_pat = fields_pat(fields);
+                            let write_fields = write_fields(internal, true, type_attrs, fields);
+                            let write_discrim = write_sync_discrim(&idx);
                             quote! {
                                 Self::#var #fields_pat => {
-                                    #idx.write_sync(sink).map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
+                                    #write_discrim.map_err(|#async_proto_crate::WriteError { context, kind }| #async_proto_crate::WriteError {
                                         context: #async_proto_crate::ErrorContext::EnumDiscrim {
                                             source: Box::new(context),
                                         },
@@ -537,7 +965,7 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
                         .collect_vec();
                     (
                         quote! {
-                            match <#discrim_ty as #async_proto_crate::Protocol>::read(stream).await.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
+                            match #read_discrim.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
                                 context: #async_proto_crate::ErrorContext::EnumDiscrim {
                                     source: Box::new(context),
                                 },
@@ -557,7 +985,7 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
                             ::core::result::Result::Ok(())
                         },
                         quote! {
-                            match <#discrim_ty as #async_proto_crate::Protocol>::read_sync(stream).map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
+                            match #read_sync_discrim.map_err(|#async_proto_crate::ReadError { context, kind }| #async_proto_crate::ReadError {
                                 context: #async_proto_crate::ErrorContext::EnumDiscrim {
                                     source: Box::new(context),
                                 },
@@ -579,12 +1007,20 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
                     )
                 }
             }
-            Some(Data::Union(_)) => return quote!(compile_error!("unions not supported in derive(Protocol)");).into(),
-            None => return quote!(compile_error!("missing type layout specification or #[async_proto(via = ...)]");).into(),
+            Some(Data::Union(_)) => return quote!(compile_error!("unions not supported in derive(Protocol)");),
+            None => return quote!(compile_error!("missing type layout specification or #[async_proto(via = ...)]");),
         }
     };
     let (impl_generics, ty_generics, where_clause) = impl_generics.split_for_impl();
+    let impl_bit_field = impl_bit_field.map(|impl_bit_field| quote! {
+        #(#[#impl_attrs])*
+        impl #impl_generics #async_proto_crate::BitField for #qual_ty #ty_generics #where_clause {
+            #impl_bit_field
+        }
+    });
     quote! {
+        #impl_bit_field
+
         #(#[#impl_attrs])*
         impl #impl_generics #async_proto_crate::Protocol for #qual_ty #ty_generics #where_clause {
             fn read<'a, R: #async_proto_crate::tokio::io::AsyncRead + ::core::marker::Unpin + ::core::marker::Send + 'a>(stream: &'a mut R) -> ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = ::core::result::Result<Self, #async_proto_crate::ReadError>> + ::core::marker::Send + 'a>> {
@@ -610,7 +1046,7 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
 ///     * For `enum`s with up to 256 variants, the discriminant is represented as a [`u8`]. For `enums` with 257 to 65536 variants, as a [`u16`], and so on.
 /// * Then follow the `Protocol` representations of any fields of the `struct` or variant, in the order declared.
 ///
-/// This representation can waste bandwidth for some types, e.g. `struct`s with multiple [`bool`] fields. For those, you may want to implement `Protocol` manually.
+/// This representation can waste bandwidth for some types, e.g. `struct`s with multiple [`bool`] fields. For those, you may want to use `#[async_proto(bitpacked)]` (see below) or implement `Protocol` manually.
 ///
 /// # Attributes
 ///
@@ -619,6 +1055,9 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
 /// * `#[async_proto(as_string)]`: Implements `Protocol` for this type by converting from and to a string using the `FromStr` and `ToString` traits. The `FromStr` error type must implement `Into<ReadErrorKind>`.
 ///     * `#[async_proto(map_err = ...)]`: Removes the requirement for the `FromStr` error type to implement `Into<ReadErrorKind>` and instead uses the given expression (which should be an `FnOnce(<T as FromStr>::Err) -> ReadErrorKind`) to convert the error.
 /// * `#[async_proto(attr(...))]`: Adds the given attribute(s) to the `Protocol` implementation. For example, the implementation can be documented using `#[async_proto(attr(doc = "..."))]`. May be specified multiple times.
+/// * `#[async_proto(bitpacked)]`: Allows packing fields into bit fields using the `#[async_proto(bits = ...)]` field attribute, see below.
+/// * `#[async_proto(endian = little)]`: Encodes the enum discriminant and all fields whose type is a primitive number type with more than one byte (e.g. [`u32`] or [`f64`]) in little-endian byte order instead of big-endian, as well as the length prefixes of fields with a `canonical`, `len`, or `max_len` attribute. Other fields are unaffected, including the length prefixes of collections without these attributes and the fields of nested types (which use their own `Protocol` implementations), and individual fields can opt back into big-endian byte order with `#[async_proto(endian = big)]`. Note that the field types are detected syntactically, so type aliases of primitive number types are not affected either.
+/// * `#[async_proto(presence_bitmap)]`: Fields whose type is `Option<T>` are represented by a bitmap written before all fields (of the `struct` or variant) with one bit per such field, indicating whether it is `Some`, followed by only the values of the fields which are `Some`, without the discriminant of the `Option`. The bitmap takes up the minimal number of bytes, starting with the most significant bit of the first byte for the first `Option` field, with any unused bits at the end set to 0. This saves space on types with many `Option` fields which are often `None`. Field attributes on `Option` fields apply to the value inside the `Option`. Note that the field types are detected syntactically, so type aliases of `Option` are not affected.
 /// * `#[async_proto(via = Proxy)]`: Implements `Protocol` for this type (let's call it `T`) in terms of another type (`Proxy` in this case) instead of using the variant- and field-based representation described above. `&'a T` must implement `TryInto<Proxy>` for all `'a`, with an `Error` type that implements `Into<WriteErrorKind>`, and `Proxy` must implement `Protocol` and `TryInto<T>`, with an `Error` type that implements `Into<ReadErrorKind>`.
 ///     * `#[async_proto(clone)]`: Replaces the requirement for `&'a T` to implement `TryInto<Proxy>` with requirements for `T` to implement `Clone` and `TryInto<Proxy>`.
 ///     * `#[async_proto(map_err = ...)]`: Removes the requirement for `<Proxy as TryInto<T>>::Error` to implement `Into<ReadErrorKind>` and instead uses the given expression (which should be an `FnOnce(<Proxy as TryInto<T>>::Error) -> ReadErrorKind`) to convert the error.
@@ -628,7 +1067,12 @@ fn impl_protocol_inner(mut internal: bool, attrs: Vec<Attribute>, qual_ty: Path,
 ///
 /// Additionally, the following attributes can be set on struct or enum fields, rather than the entire type for which `Protocol` is being derived:
 ///
-/// * `#[async_proto(max_len = ...)]`: Can be used on a field implementing the `LengthPrefixed` trait to limit the allowable length. Note that this alters the network representation of the length prefix (with a `max_len` of up to 255, the length is represented as a [`u8`]; with a `max_len` of 256 to 65535, as a [`u16`]; and so on), so adding/removing/changing this attribute may break protocol compatibility.
+/// * `#[async_proto(bits = N)]`: Requires `#[async_proto(bitpacked)]` on the type. Stores this field in `N` bits (between 1 and 128) instead of its usual representation. The field type must implement the `BitField` trait, which is implemented for [`bool`], the primitive integer types (with signed integers represented in two's complement), and enums deriving `Protocol` where no variant has any fields (represented by the variant index). Each run of consecutive bit fields is packed into the minimal number of bytes, starting with the most significant bit of the first byte, with any unused bits at the end set to 0. Writing a value which doesn't fit into `N` bits is an error.
+/// * `#[async_proto(canonical)]`: Can be used on a field implementing the `CanonicalEncoding` trait (e.g. a `HashMap` or `HashSet`) to write it in a deterministic order, sorted by the encoded keys, and to reject input which is not in that order or contains duplicate keys. The network representation is otherwise unchanged. Can be combined with `len`, `max_len`, and `varint` to choose the length prefix.
+/// * `#[async_proto(endian = big)]` or `#[async_proto(endian = little)]`: Encodes this field using the given byte order by reading/writing it via the `Be` or `Le` wrapper type. The field type must be a primitive number type, unless the field also has a `canonical`, `len`, or `max_len` attribute, in which case the byte order applies to its length prefix instead.
+/// * `#[async_proto(len = ...)]`: Can be used on a field implementing the `LengthPrefixed` trait to choose how the length is represented, independently of `max_len`. Valid values are `u8`, `u16`, `u32`, `u64`, and `varint`. With a fixed-width integer type, lengths which don't fit into that type are rejected, in addition to the limit imposed by `max_len`.
+/// * `#[async_proto(max_len = ...)]`: Can be used on a field implementing the `LengthPrefixed` trait to limit the allowable length. Unless `len` is also specified, note that this alters the network representation of the length prefix (with a `max_len` of up to 255, the length is represented as a [`u8`]; with a `max_len` of 256 to 65535, as a [`u16`]; and so on), so adding/removing/changing this attribute may break protocol compatibility.
+/// * `#[async_proto(varint)]`: Encodes this field as a variable-length integer. On primitive unsigned integer fields, this reads/writes the field via the `Varint` wrapper type; on signed ones, via the `ZigZag` wrapper type. On fields implementing the `LengthPrefixed` trait, this is equivalent to `#[async_proto(len = varint)]`.
 ///
 /// # Compile errors
 ///
@@ -721,7 +1165,7 @@ impl Parse for Bitflags {
 ///
 /// # Usage
 ///
-/// ```rust
+/// ```rust,ignore
 /// bitflags::bitflags! {
 ///     struct Flags: u32 {
 ///         const A = 0b00000001;
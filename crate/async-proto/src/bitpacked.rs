@@ -0,0 +1,129 @@
+//! Support for types whose `Protocol` implementation was derived with `#[async_proto(bitpacked)]`.
+
+use crate::ReadErrorKind;
+
+/// A type which can be stored in a bit field of a type whose [`Protocol`](crate::Protocol) implementation was derived with `#[async_proto(bitpacked)]`.
+///
+/// This is implemented for [`bool`] and the primitive integer types, and derived along with [`Protocol`](crate::Protocol) for enums where no variant has any fields. Such enums are represented by the index of the variant, starting at 0.
+pub trait BitField: Sized {
+    /// Returns the lowest `bits` bits representing this value, or [`None`] if it can't be represented using that many bits.
+    ///
+    /// `bits` is between 1 and 128 inclusive. All bits above the lowest `bits` bits of the return value must be 0.
+    fn to_bits(&self, bits: u32) -> Option<u128>;
+    /// Converts the lowest `bits` bits of `value` back into this type.
+    ///
+    /// `bits` is between 1 and 128 inclusive. All bits above the lowest `bits` bits of `value` are 0.
+    fn from_bits(value: u128, bits: u32) -> Result<Self, ReadErrorKind>;
+}
+
+impl BitField for bool {
+    fn to_bits(&self, _: u32) -> Option<u128> {
+        Some((*self).into())
+    }
+
+    fn from_bits(value: u128, _: u32) -> Result<Self, ReadErrorKind> {
+        match value {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ReadErrorKind::BitFieldValue(value)),
+        }
+    }
+}
+
+macro_rules! impl_bit_field_unsigned {
+    ($ty:ty) => {
+        impl BitField for $ty {
+            fn to_bits(&self, bits: u32) -> Option<u128> {
+                let value = u128::from(*self);
+                (bits >= u128::BITS || value >> bits == 0).then_some(value)
+            }
+
+            fn from_bits(value: u128, _: u32) -> Result<Self, ReadErrorKind> {
+                Self::try_from(value).map_err(|_| ReadErrorKind::BitFieldValue(value))
+            }
+        }
+    };
+}
+
+impl_bit_field_unsigned!(u8);
+impl_bit_field_unsigned!(u16);
+impl_bit_field_unsigned!(u32);
+impl_bit_field_unsigned!(u64);
+impl_bit_field_unsigned!(u128);
+
+// signed integers are stored in two's complement
+macro_rules! impl_bit_field_signed {
+    ($ty:ty) => {
+        impl BitField for $ty {
+            fn to_bits(&self, bits: u32) -> Option<u128> {
+                let value = i128::from(*self);
+                let unused = u128::BITS.saturating_sub(bits);
+                // the value fits iff it survives truncation to `bits` bits followed by sign extension
+                (value << unused >> unused == value).then(|| value as u128 & (u128::MAX >> unused))
+            }
+
+            fn from_bits(value: u128, bits: u32) -> Result<Self, ReadErrorKind> {
+                let unused = u128::BITS.saturating_sub(bits);
+                Self::try_from((value << unused) as i128 >> unused).map_err(|_| ReadErrorKind::BitFieldValue(value))
+            }
+        }
+    };
+}
+
+impl_bit_field_signed!(i8);
+impl_bit_field_signed!(i16);
+impl_bit_field_signed!(i32);
+impl_bit_field_signed!(i64);
+impl_bit_field_signed!(i128);
+
+/// Packs bit fields into a byte array, starting with the most significant bit of the first byte. Used in the output of `#[derive(Protocol)]`.
+#[doc(hidden)]
+pub struct BitWriter<const N: usize> {
+    buf: [u8; N],
+    pos: usize,
+}
+
+impl<const N: usize> Default for BitWriter<N> {
+    fn default() -> Self {
+        Self { buf: [0; N], pos: 0 }
+    }
+}
+
+impl<const N: usize> BitWriter<N> {
+    /// Appends the lowest `bits` bits of `value`, most significant first.
+    pub fn write(&mut self, value: u128, bits: u32) {
+        for bit in (0..bits).rev() {
+            if value >> bit & 1 != 0 {
+                self.buf[self.pos / 8] |= 0x80 >> (self.pos % 8);
+            }
+            self.pos += 1;
+        }
+    }
+
+    pub fn into_bytes(self) -> [u8; N] {
+        self.buf
+    }
+}
+
+/// Unpacks bit fields written by a [`BitWriter`]. Used in the output of `#[derive(Protocol)]`.
+#[doc(hidden)]
+pub struct BitReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    /// Takes the next `bits` bits, most significant first.
+    pub fn read(&mut self, bits: u32) -> u128 {
+        let mut value = 0;
+        for _ in 0..bits {
+            value = value << 1 | u128::from(self.buf[self.pos / 8] >> (7 - self.pos % 8) & 1);
+            self.pos += 1;
+        }
+        value
+    }
+}
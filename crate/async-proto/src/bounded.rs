@@ -0,0 +1,335 @@
+//! Collection and string types with a maximum length that is part of the type.
+
+use {
+    std::{
+        future::Future,
+        io::prelude::*,
+        ops::{
+            Deref,
+            DerefMut,
+        },
+        pin::Pin,
+    },
+    tokio::io::{
+        AsyncRead,
+        AsyncWrite,
+    },
+    crate::{
+        LengthPrefix,
+        LengthPrefixed,
+        Protocol,
+        ReadError,
+        WriteError,
+        impls::{
+            read_bytes,
+            read_bytes_sync,
+            write_bytes,
+            write_bytes_sync,
+        },
+    },
+};
+
+/// The error returned when attempting to construct a [`BoundedVec`], [`BoundedString`], or [`BoundedBytes`] from a value which is too long.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("length exceeds the maximum length of {max_len}")]
+pub struct MaxLenError<T> {
+    /// The value which was too long.
+    pub value: T,
+    /// The maximum length of the type which was being constructed.
+    pub max_len: u64,
+}
+
+/// Returns whether a value of the given length fits into a bounded type with the given maximum length.
+fn fits(len: usize, max_len: u64) -> bool {
+    u64::try_from(len).is_ok_and(|len| len <= max_len)
+}
+
+/// Limits the given length prefix to the given maximum length, keeping its representation and byte order.
+fn bounded_prefix(prefix: LengthPrefix, max_len: u64) -> LengthPrefix {
+    LengthPrefix {
+        max_len: prefix.max_len.min(max_len),
+        repr: prefix.repr,
+        endian: prefix.endian,
+    }
+}
+
+/// A [`Vec`] with at most `MAX` elements.
+///
+/// This is represented like a `Vec<T>` with `#[async_proto(max_len = MAX)]`, i.e. the length is represented using the smallest unsigned integer type which can represent `MAX`. Unlike the field attribute, the limit is part of the type, so it is enforced wherever the value is read, e.g. when nested inside another collection. It's also enforced when constructing a value of this type.
+///
+/// To limit the length of each element as well, use a bounded element type, e.g. `BoundedVec<BoundedString<64>, 16>`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoundedVec<T, const MAX: u64>(Vec<T>);
+
+impl<T, const MAX: u64> BoundedVec<T, MAX> {
+    /// Returns an error if the given [`Vec`] has more than `MAX` elements.
+    pub fn new(vec: Vec<T>) -> Result<Self, MaxLenError<Vec<T>>> {
+        if fits(vec.len(), MAX) {
+            Ok(Self(vec))
+        } else {
+            Err(MaxLenError { value: vec, max_len: MAX })
+        }
+    }
+
+    /// Appends an element, or returns it if this already has `MAX` elements.
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        if fits(self.0.len() + 1, MAX) {
+            self.0.push(value);
+            Ok(())
+        } else {
+            Err(value)
+        }
+    }
+
+    /// Returns the underlying [`Vec`].
+    pub fn into_inner(self) -> Vec<T> { self.0 }
+}
+
+impl<T, const MAX: u64> TryFrom<Vec<T>> for BoundedVec<T, MAX> {
+    type Error = MaxLenError<Vec<T>>;
+
+    fn try_from(vec: Vec<T>) -> Result<Self, MaxLenError<Vec<T>>> {
+        Self::new(vec)
+    }
+}
+
+impl<T, const MAX: u64> From<BoundedVec<T, MAX>> for Vec<T> {
+    fn from(BoundedVec(vec): BoundedVec<T, MAX>) -> Self {
+        vec
+    }
+}
+
+impl<T, const MAX: u64> Deref for BoundedVec<T, MAX> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] { &self.0 }
+}
+
+impl<T, const MAX: u64> DerefMut for BoundedVec<T, MAX> {
+    fn deref_mut(&mut self) -> &mut [T] { &mut self.0 }
+}
+
+impl<T: Protocol + Send + Sync, const MAX: u64> Protocol for BoundedVec<T, MAX> {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, LengthPrefix::default())
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, LengthPrefix::default())
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
+    }
+}
+
+/// The given length prefix is further limited to `MAX`.
+impl<T: Protocol + Send + Sync, const MAX: u64> LengthPrefixed for BoundedVec<T, MAX> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(Self(Vec::read_length_prefixed(stream, bounded_prefix(prefix, MAX)).await?))
+        })
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.0.write_length_prefixed(sink, bounded_prefix(prefix, MAX))
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Ok(Self(Vec::read_length_prefixed_sync(stream, bounded_prefix(prefix, MAX))?))
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        self.0.write_length_prefixed_sync(sink, bounded_prefix(prefix, MAX))
+    }
+}
+
+/// A [`String`] with a length of at most `MAX` bytes.
+///
+/// This is represented like a `String` with `#[async_proto(max_len = MAX)]`, see [`BoundedVec`] for details.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoundedString<const MAX: u64>(String);
+
+impl<const MAX: u64> BoundedString<MAX> {
+    /// Returns an error if the given [`String`] is longer than `MAX` bytes.
+    pub fn new(s: String) -> Result<Self, MaxLenError<String>> {
+        if fits(s.len(), MAX) {
+            Ok(Self(s))
+        } else {
+            Err(MaxLenError { value: s, max_len: MAX })
+        }
+    }
+
+    /// Returns the underlying [`String`].
+    pub fn into_inner(self) -> String { self.0 }
+}
+
+impl<const MAX: u64> TryFrom<String> for BoundedString<MAX> {
+    type Error = MaxLenError<String>;
+
+    fn try_from(s: String) -> Result<Self, MaxLenError<String>> {
+        Self::new(s)
+    }
+}
+
+impl<'a, const MAX: u64> TryFrom<&'a str> for BoundedString<MAX> {
+    type Error = MaxLenError<&'a str>;
+
+    fn try_from(s: &'a str) -> Result<Self, MaxLenError<&'a str>> {
+        if fits(s.len(), MAX) {
+            Ok(Self(s.to_owned()))
+        } else {
+            Err(MaxLenError { value: s, max_len: MAX })
+        }
+    }
+}
+
+impl<const MAX: u64> From<BoundedString<MAX>> for String {
+    fn from(BoundedString(s): BoundedString<MAX>) -> Self {
+        s
+    }
+}
+
+impl<const MAX: u64> Deref for BoundedString<MAX> {
+    type Target = str;
+
+    fn deref(&self) -> &str { &self.0 }
+}
+
+impl<const MAX: u64> Protocol for BoundedString<MAX> {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, LengthPrefix::default())
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, LengthPrefix::default())
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
+    }
+}
+
+/// The given length prefix is further limited to `MAX`.
+impl<const MAX: u64> LengthPrefixed for BoundedString<MAX> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(Self(String::read_length_prefixed(stream, bounded_prefix(prefix, MAX)).await?))
+        })
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.0.write_length_prefixed(sink, bounded_prefix(prefix, MAX))
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Ok(Self(String::read_length_prefixed_sync(stream, bounded_prefix(prefix, MAX))?))
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        self.0.write_length_prefixed_sync(sink, bounded_prefix(prefix, MAX))
+    }
+}
+
+/// A byte buffer with a length of at most `MAX` bytes.
+///
+/// This is represented like a `Vec<u8>` with `#[async_proto(max_len = MAX)]`, see [`BoundedVec`] for details, but read and written as a single buffer rather than byte by byte.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoundedBytes<const MAX: u64>(Vec<u8>);
+
+impl<const MAX: u64> BoundedBytes<MAX> {
+    /// Returns an error if the given buffer is longer than `MAX` bytes.
+    pub fn new(bytes: Vec<u8>) -> Result<Self, MaxLenError<Vec<u8>>> {
+        if fits(bytes.len(), MAX) {
+            Ok(Self(bytes))
+        } else {
+            Err(MaxLenError { value: bytes, max_len: MAX })
+        }
+    }
+
+    /// Returns the underlying buffer.
+    pub fn into_inner(self) -> Vec<u8> { self.0 }
+}
+
+impl<const MAX: u64> TryFrom<Vec<u8>> for BoundedBytes<MAX> {
+    type Error = MaxLenError<Vec<u8>>;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, MaxLenError<Vec<u8>>> {
+        Self::new(bytes)
+    }
+}
+
+impl<'a, const MAX: u64> TryFrom<&'a [u8]> for BoundedBytes<MAX> {
+    type Error = MaxLenError<&'a [u8]>;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, MaxLenError<&'a [u8]>> {
+        if fits(bytes.len(), MAX) {
+            Ok(Self(bytes.to_owned()))
+        } else {
+            Err(MaxLenError { value: bytes, max_len: MAX })
+        }
+    }
+}
+
+impl<const MAX: u64> From<BoundedBytes<MAX>> for Vec<u8> {
+    fn from(BoundedBytes(bytes): BoundedBytes<MAX>) -> Self {
+        bytes
+    }
+}
+
+impl<const MAX: u64> Deref for BoundedBytes<MAX> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] { &self.0 }
+}
+
+impl<const MAX: u64> DerefMut for BoundedBytes<MAX> {
+    fn deref_mut(&mut self) -> &mut [u8] { &mut self.0 }
+}
+
+impl<const MAX: u64> Protocol for BoundedBytes<MAX> {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, LengthPrefix::default())
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, LengthPrefix::default())
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
+    }
+}
+
+/// The given length prefix is further limited to `MAX`.
+impl<const MAX: u64> LengthPrefixed for BoundedBytes<MAX> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(Self(read_bytes(stream, bounded_prefix(prefix, MAX), "BoundedBytes").await?))
+        })
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(write_bytes(sink, &self.0, bounded_prefix(prefix, MAX), "BoundedBytes"))
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Ok(Self(read_bytes_sync(stream, bounded_prefix(prefix, MAX), "BoundedBytes")?))
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_bytes_sync(sink, &self.0, bounded_prefix(prefix, MAX), "BoundedBytes")
+    }
+}
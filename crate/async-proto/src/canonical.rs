@@ -0,0 +1,291 @@
+//! Deterministic encoding of hash-based collections.
+
+use {
+    std::{
+        collections::{
+            HashMap,
+            HashSet,
+        },
+        future::Future,
+        hash::{
+            BuildHasher,
+            Hash,
+        },
+        io::prelude::*,
+        pin::Pin,
+    },
+    tokio::io::{
+        AsyncRead,
+        AsyncWrite,
+        AsyncWriteExt as _,
+    },
+    crate::{
+        ErrorContext,
+        LengthPrefix,
+        LengthPrefixed,
+        Protocol,
+        ReadError,
+        ReadErrorKind,
+        WriteError,
+        impls::{
+            read_len,
+            read_len_sync,
+            write_len,
+            write_len_sync,
+        },
+    },
+};
+
+/// This trait allows writing collections whose iteration order is unspecified (e.g. [`HashMap`] and [`HashSet`]) in a deterministic order, so that equal values always have the same representation.
+///
+/// In canonical mode, the entries of a collection are written sorted by the bytes of their encoded keys (or elements, for sets). The representation is otherwise the same as that of the [`LengthPrefixed`] impl, so values written in canonical mode can be read normally and vice versa. Reading in canonical mode additionally rejects input whose entries are not sorted this way or which contains duplicate keys, with [`ReadErrorKind::NonCanonical`].
+///
+/// Only the order of the collection itself is affected. Nested hash-based collections, e.g. the values of a `HashMap<String, HashSet<String>>`, are written in iteration order unless they're wrapped in [`Canonical`].
+///
+/// Fields can be read and written in canonical mode using the `#[async_proto(canonical)]` attribute when deriving [`Protocol`], and other values using the [`Canonical`] wrapper type or the methods of this trait.
+pub trait CanonicalEncoding: LengthPrefixed {
+    /// Reads a value of this type from an async stream, with the length limited and represented as specified, and rejects it if it's not in canonical order.
+    fn read_canonical<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>>;
+    /// Writes a value of this type to an async sink in canonical order, with the length limited and represented as specified.
+    fn write_canonical<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>>;
+    /// Reads a value of this type from a sync stream, with the length limited and represented as specified, and rejects it if it's not in canonical order.
+    fn read_canonical_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError>;
+    /// Writes a value of this type to a sync sink in canonical order, with the length limited and represented as specified.
+    fn write_canonical_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError>;
+}
+
+/// A wrapper type which reads and writes the inner value in canonical mode, see [`CanonicalEncoding`].
+///
+/// This can be used to encode hash-based collections deterministically where the `#[async_proto(canonical)]` attribute is not available, e.g. for the elements of a [`Vec`] or when hashing or signing a single value.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Canonical<T>(pub T);
+
+impl<T: CanonicalEncoding + Send + Sync> Protocol for Canonical<T> {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, LengthPrefix::default())
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, LengthPrefix::default())
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
+    }
+}
+
+impl<T: CanonicalEncoding + Send + Sync> LengthPrefixed for Canonical<T> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(Self(T::read_canonical(stream, prefix).await?))
+        })
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.0.write_canonical(sink, prefix)
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Ok(Self(T::read_canonical_sync(stream, prefix)?))
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        self.0.write_canonical_sync(sink, prefix)
+    }
+}
+
+impl<T: CanonicalEncoding + Send + Sync> CanonicalEncoding for Canonical<T> {
+    fn read_canonical<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, prefix)
+    }
+
+    fn write_canonical<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, prefix)
+    }
+
+    fn read_canonical_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, prefix)
+    }
+
+    fn write_canonical_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, prefix)
+    }
+}
+
+/// Encodes the keys of the given entries and sorts the entries by their encoded keys.
+fn sorted_entries<'a, K: Protocol + 'a, V>(entries: impl Iterator<Item = (&'a K, V)>) -> Result<Vec<(Vec<u8>, V)>, WriteError> {
+    let mut sorted = entries.map(|(k, v)| {
+        let mut buf = Vec::default();
+        k.write_sync(&mut buf)?;
+        Ok((buf, v))
+    }).collect::<Result<Vec<_>, WriteError>>()?;
+    sorted.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+    Ok(sorted)
+}
+
+async fn write_entries<'a, V: Protocol + Sync, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, entries: Vec<(Vec<u8>, &'a V)>, prefix: LengthPrefix, for_type: &'static str) -> Result<(), WriteError> {
+    write_len(sink, entries.len(), prefix, || ErrorContext::BuiltIn { for_type }).await?;
+    for (k, v) in entries {
+        sink.write_all(&k).await.map_err(|e| WriteError {
+            context: ErrorContext::BuiltIn { for_type },
+            kind: e.into(),
+        })?;
+        v.write(sink).await?;
+    }
+    Ok(())
+}
+
+fn write_entries_sync<V: Protocol>(sink: &mut impl Write, entries: Vec<(Vec<u8>, &V)>, prefix: LengthPrefix, for_type: &'static str) -> Result<(), WriteError> {
+    write_len_sync(sink, entries.len(), prefix, || ErrorContext::BuiltIn { for_type })?;
+    for (k, v) in entries {
+        sink.write_all(&k).map_err(|e| WriteError {
+            context: ErrorContext::BuiltIn { for_type },
+            kind: e.into(),
+        })?;
+        v.write_sync(sink)?;
+    }
+    Ok(())
+}
+
+/// Checks that the given key, which was just read, sorts strictly after the previously read key.
+fn check_order(prev: &mut Option<Vec<u8>>, key: &impl Protocol, for_type: &'static str) -> Result<(), ReadError> {
+    let mut buf = Vec::default();
+    // a key which can't be written can't have been written in canonical order either
+    key.write_sync(&mut buf).map_err(|_| non_canonical(for_type))?;
+    if prev.as_ref().is_some_and(|prev| *prev >= buf) { return Err(non_canonical(for_type)) }
+    *prev = Some(buf);
+    Ok(())
+}
+
+fn non_canonical(for_type: &'static str) -> ReadError {
+    ReadError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: ReadErrorKind::NonCanonical,
+    }
+}
+
+impl<T: Protocol + Eq + Hash + Send + Sync, S: BuildHasher + Default + Send + Sync> CanonicalEncoding for HashSet<T, S> {
+    fn read_canonical<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "HashSet" }).await?;
+            let mut set = Self::with_capacity_and_hasher(len, S::default()); //TODO use fallible allocation once available
+            let mut prev = None;
+            for _ in 0..len {
+                let elt = T::read(stream).await?;
+                check_order(&mut prev, &elt, "HashSet")?;
+                if !set.insert(elt) { return Err(non_canonical("HashSet")) }
+            }
+            Ok(set)
+        })
+    }
+
+    fn write_canonical<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(async move {
+            let entries = sorted_entries(self.iter().map(|elt| (elt, &())))?;
+            write_entries(sink, entries, prefix, "HashSet").await
+        })
+    }
+
+    fn read_canonical_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "HashSet" })?;
+        let mut set = Self::with_capacity_and_hasher(len, S::default()); //TODO use fallible allocation once available
+        let mut prev = None;
+        for _ in 0..len {
+            let elt = T::read_sync(stream)?;
+            check_order(&mut prev, &elt, "HashSet")?;
+            if !set.insert(elt) { return Err(non_canonical("HashSet")) }
+        }
+        Ok(set)
+    }
+
+    fn write_canonical_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        let entries = sorted_entries(self.iter().map(|elt| (elt, &())))?;
+        write_entries_sync(sink, entries, prefix, "HashSet")
+    }
+}
+
+impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync, S: BuildHasher + Default + Send + Sync> CanonicalEncoding for HashMap<K, V, S> {
+    fn read_canonical<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "HashMap" }).await?;
+            let mut map = Self::with_capacity_and_hasher(len, S::default()); //TODO use fallible allocation once available
+            let mut prev = None;
+            for _ in 0..len {
+                let k = K::read(stream).await?;
+                check_order(&mut prev, &k, "HashMap")?;
+                if map.insert(k, V::read(stream).await?).is_some() { return Err(non_canonical("HashMap")) }
+            }
+            Ok(map)
+        })
+    }
+
+    fn write_canonical<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(async move {
+            let entries = sorted_entries(self.iter())?;
+            write_entries(sink, entries, prefix, "HashMap").await
+        })
+    }
+
+    fn read_canonical_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "HashMap" })?;
+        let mut map = Self::with_capacity_and_hasher(len, S::default()); //TODO use fallible allocation once available
+        let mut prev = None;
+        for _ in 0..len {
+            let k = K::read_sync(stream)?;
+            check_order(&mut prev, &k, "HashMap")?;
+            if map.insert(k, V::read_sync(stream)?).is_some() { return Err(non_canonical("HashMap")) }
+        }
+        Ok(map)
+    }
+
+    fn write_canonical_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        let entries = sorted_entries(self.iter())?;
+        write_entries_sync(sink, entries, prefix, "HashMap")
+    }
+}
+
+#[cfg(feature = "serde_json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde_json")))]
+impl CanonicalEncoding for serde_json::Map<String, serde_json::Value> {
+    fn read_canonical<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "serde_json::Map" }).await?;
+            let mut map = Self::with_capacity(len); //TODO fallible allocation?
+            let mut prev = None;
+            for _ in 0..len {
+                let k = String::read(stream).await?;
+                check_order(&mut prev, &k, "serde_json::Map")?;
+                if map.insert(k, serde_json::Value::read(stream).await?).is_some() { return Err(non_canonical("serde_json::Map")) }
+            }
+            Ok(map)
+        })
+    }
+
+    fn write_canonical<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(async move {
+            let entries = sorted_entries(self.iter())?;
+            write_entries(sink, entries, prefix, "serde_json::Map").await
+        })
+    }
+
+    fn read_canonical_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "serde_json::Map" })?;
+        let mut map = Self::with_capacity(len); //TODO fallible allocation?
+        let mut prev = None;
+        for _ in 0..len {
+            let k = String::read_sync(stream)?;
+            check_order(&mut prev, &k, "serde_json::Map")?;
+            if map.insert(k, serde_json::Value::read_sync(stream)?).is_some() { return Err(non_canonical("serde_json::Map")) }
+        }
+        Ok(map)
+    }
+
+    fn write_canonical_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        let entries = sorted_entries(self.iter())?;
+        write_entries_sync(sink, entries, prefix, "serde_json::Map")
+    }
+}
@@ -0,0 +1,115 @@
+//! Wrapper types for choosing the byte order of primitive number types.
+
+use {
+    std::{
+        future::Future,
+        io::prelude::*,
+        pin::Pin,
+    },
+    byteorder::{
+        BigEndian,
+        LittleEndian,
+        ReadBytesExt as _,
+        WriteBytesExt as _,
+    },
+    tokio::io::{
+        AsyncRead,
+        AsyncReadExt as _,
+        AsyncWrite,
+        AsyncWriteExt as _,
+    },
+    crate::{
+        ErrorContext,
+        Protocol,
+        ReadError,
+        WriteError,
+    },
+};
+
+/// A [byte order](https://en.wikipedia.org/wiki/Endianness), e.g. for the length prefix of a [`LengthPrefixed`](crate::LengthPrefixed) value, see [`LengthPrefix`](crate::LengthPrefix).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endian {
+    /// The most significant byte comes first. This is the default byte order of this crate.
+    #[default]
+    Big,
+    /// The least significant byte comes first.
+    Little,
+}
+
+/// A primitive number type encoded in [little-endian](https://en.wikipedia.org/wiki/Endianness) byte order.
+///
+/// [`Protocol`] is implemented for `Le<T>` for all primitive integer and floating-point types `T`. This is useful for speaking existing binary formats which use little-endian byte order.
+///
+/// When deriving [`Protocol`], the `#[async_proto(endian = little)]` attribute can be used instead of wrapping fields in this type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Le<T>(pub T);
+
+/// A primitive number type encoded in [big-endian](https://en.wikipedia.org/wiki/Endianness) byte order.
+///
+/// Since this is the default byte order for primitive number types, `Be<T>` is represented the same way as `T`. This type exists to make the byte order explicit, e.g. for a big-endian field in an otherwise little-endian type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Be<T>(pub T);
+
+macro_rules! impl_protocol_endian {
+    ($wrapper:ident, $ty:ty, $read:ident, $write:ident, $read_sync:ident, $write_sync:ident$(, $endian:ty)?) => {
+        impl Protocol for $wrapper<$ty> {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    Ok(Self(stream.$read().await.map_err(|e| ReadError {
+                        context: ErrorContext::BuiltIn { for_type: concat!(stringify!($wrapper), "<", stringify!($ty), ">") },
+                        kind: e.into(),
+                    })?))
+                })
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                Box::pin(async move {
+                    sink.$write(self.0).await.map_err(|e| WriteError {
+                        context: ErrorContext::BuiltIn { for_type: concat!(stringify!($wrapper), "<", stringify!($ty), ">") },
+                        kind: e.into(),
+                    })
+                })
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                Ok(Self(stream.$read_sync$(::<$endian>)?().map_err(|e| ReadError {
+                    context: ErrorContext::BuiltIn { for_type: concat!(stringify!($wrapper), "<", stringify!($ty), ">") },
+                    kind: e.into(),
+                })?))
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                sink.$write_sync$(::<$endian>)?(self.0).map_err(|e| WriteError {
+                    context: ErrorContext::BuiltIn { for_type: concat!(stringify!($wrapper), "<", stringify!($ty), ">") },
+                    kind: e.into(),
+                })
+            }
+        }
+    };
+}
+
+impl_protocol_endian!(Le, u8, read_u8, write_u8, read_u8, write_u8);
+impl_protocol_endian!(Le, i8, read_i8, write_i8, read_i8, write_i8);
+impl_protocol_endian!(Le, u16, read_u16_le, write_u16_le, read_u16, write_u16, LittleEndian);
+impl_protocol_endian!(Le, i16, read_i16_le, write_i16_le, read_i16, write_i16, LittleEndian);
+impl_protocol_endian!(Le, u32, read_u32_le, write_u32_le, read_u32, write_u32, LittleEndian);
+impl_protocol_endian!(Le, i32, read_i32_le, write_i32_le, read_i32, write_i32, LittleEndian);
+impl_protocol_endian!(Le, u64, read_u64_le, write_u64_le, read_u64, write_u64, LittleEndian);
+impl_protocol_endian!(Le, i64, read_i64_le, write_i64_le, read_i64, write_i64, LittleEndian);
+impl_protocol_endian!(Le, u128, read_u128_le, write_u128_le, read_u128, write_u128, LittleEndian);
+impl_protocol_endian!(Le, i128, read_i128_le, write_i128_le, read_i128, write_i128, LittleEndian);
+impl_protocol_endian!(Le, f32, read_f32_le, write_f32_le, read_f32, write_f32, LittleEndian);
+impl_protocol_endian!(Le, f64, read_f64_le, write_f64_le, read_f64, write_f64, LittleEndian);
+
+impl_protocol_endian!(Be, u8, read_u8, write_u8, read_u8, write_u8);
+impl_protocol_endian!(Be, i8, read_i8, write_i8, read_i8, write_i8);
+impl_protocol_endian!(Be, u16, read_u16, write_u16, read_u16, write_u16, BigEndian);
+impl_protocol_endian!(Be, i16, read_i16, write_i16, read_i16, write_i16, BigEndian);
+impl_protocol_endian!(Be, u32, read_u32, write_u32, read_u32, write_u32, BigEndian);
+impl_protocol_endian!(Be, i32, read_i32, write_i32, read_i32, write_i32, BigEndian);
+impl_protocol_endian!(Be, u64, read_u64, write_u64, read_u64, write_u64, BigEndian);
+impl_protocol_endian!(Be, i64, read_i64, write_i64, read_i64, write_i64, BigEndian);
+impl_protocol_endian!(Be, u128, read_u128, write_u128, read_u128, write_u128, BigEndian);
+impl_protocol_endian!(Be, i128, read_i128, write_i128, read_i128, write_i128, BigEndian);
+impl_protocol_endian!(Be, f32, read_f32, write_f32, read_f32, write_f32, BigEndian);
+impl_protocol_endian!(Be, f64, read_f64, write_f64, read_f64, write_f64, BigEndian);
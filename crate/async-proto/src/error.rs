@@ -11,6 +11,9 @@ use std::{
 #[derive(Debug, thiserror::Error)]
 #[allow(missing_docs)]
 pub enum ReadErrorKind {
+    /// Received a bit field value which is not valid for the field's type, e.g. a value of 300 for a `u8` field.
+    #[error("received an out-of-range bit field value: {0}")]
+    BitFieldValue(u128),
     /// Received a buffer with more than [`usize::MAX`] elements
     #[error("received a buffer with more than usize::MAX elements: {0}")]
     BufSize(#[from] std::num::TryFromIntError),
@@ -22,13 +25,33 @@ pub enum ReadErrorKind {
     /// Note that this error condition may also be represented as a [`ReadErrorKind::Io`] with [`kind`](io::Error::kind) [`UnexpectedEof`](io::ErrorKind::UnexpectedEof).
     #[error("reached end of stream")]
     EndOfStream,
+    #[cfg(feature = "digest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    /// Received a [`Hashed`](crate::Hashed) value whose digest does not match the received value.
+    #[error("received digest does not match the received value")]
+    DigestMismatch,
     #[error("received an infinite or NaN number")]
     FloatNotFinite,
+    /// Received a [`char`] value which is not a Unicode scalar value, e.g. a surrogate code point.
+    #[error("received an invalid char value: {0:#x}")]
+    InvalidChar(u32),
+    /// Received a [`usize`] or [`isize`] value which does not fit into the respective type on this platform.
+    #[error("received an integer which does not fit into {for_type} on this platform: {value}")]
+    IntOutOfRange {
+        for_type: &'static str,
+        value: i128,
+    },
+    /// Received a [`FixedStr`](crate::FixedStr) with a NUL byte followed by other bytes, i.e. the padding is not all NUL bytes.
+    #[error("received a fixed-length string with a NUL byte followed by non-NUL bytes")]
+    InteriorNul,
     #[error("received length ({len}) exceeds specified maximum length ({max_len})")]
     MaxLen {
         len: u64,
         max_len: u64,
     },
+    /// While reading in canonical mode (see [`CanonicalEncoding`](crate::CanonicalEncoding)), received a collection whose entries are not sorted by their encoded keys or which contains duplicate keys.
+    #[error("received a collection which is not in canonical order")]
+    NonCanonical,
     #[cfg(feature = "tokio-tungstenite021")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
     /// Received a non-`Binary` WebSocket message (e.g. `Text` or `Ping`).
@@ -47,6 +70,18 @@ pub enum ReadErrorKind {
     /// Attempted to read an empty type
     #[error("attempted to read an empty type")]
     ReadNever,
+    /// Received a [`SystemTime`](std::time::SystemTime) which can't be represented on this platform.
+    #[error("received an out-of-range system time: {secs}s + {subsec_nanos}ns since the Unix epoch")]
+    SystemTimeOutOfRange {
+        secs: i64,
+        subsec_nanos: u32,
+    },
+    /// A value decoded from a buffer of known length, e.g. by [`Lazy::get`](crate::Lazy::get), did not take up the entire buffer.
+    #[error("buffer contained {len} bytes but the value only took up {read} bytes")]
+    TrailingData {
+        len: u64,
+        read: u64,
+    },
     #[error("{0:?}")] // fallible_collections::TryReserveError does not implement Error, see https://github.com/vcombey/fallible_collections/pull/44
     TryReserve(fallible_collections::TryReserveError),
     #[error("unknown enum variant: {0}")]
@@ -59,28 +94,63 @@ pub enum ReadErrorKind {
     UnknownVariant64(u64),
     #[error("unknown enum variant: {0}")]
     UnknownVariant128(u128),
-    #[cfg(any(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024")))]
-    #[cfg_attr(docsrs, doc(cfg(any(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024")))))]
+    /// Received a varint with trailing zero groups, i.e. a value that could have been encoded in fewer bytes.
+    #[error("received a varint which is not minimally encoded")]
+    VarintOverlong,
+    /// Received a varint whose value does not fit into the target integer type.
+    #[error("received a varint which does not fit into {bits} bits")]
+    VarintOverflow {
+        bits: u32,
+    },
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// A value sent in chunks over a WebSocket was decoded before all of the announced data was read.
+    #[error("WebSocket message announced {len} bytes but the value only took up {read} bytes")]
+    WebSocketTrailingData {
+        len: u64,
+        read: u64,
+    },
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// The peer closed the WebSocket connection, with the given close code and reason if any.
+    ///
+    /// The typed WebSocket streams returned from functions like `websocket029` end without an error instead if the connection was closed cleanly (with close code 1000 or 1001, or without a close code) between messages.
+    #[error("WebSocket connection closed by peer")]
+    WebSocketClosed(Option<crate::WsCloseFrame>),
+    #[cfg(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024"))))]
     #[error("unexpected text message received from WebSocket: {0}")]
     WebSocketTextMessage024(String),
-    #[cfg(any(feature = "tokio-tungstenite029"))]
-    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio-tungstenite029"))))]
+    #[cfg(feature = "tokio-tungstenite029")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
     #[error("unexpected text message received from WebSocket: {0}")]
     WebSocketTextMessage029(tungstenite029::Utf8Bytes),
     #[error(transparent)] Io(#[from] io::Error),
-    #[cfg(any(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024", feature = "tokio-tungstenite029")))]
-    #[cfg_attr(docsrs, doc(cfg(any(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024", feature = "tokio-tungstenite029")))))]
+    #[cfg(feature = "websocket-json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket-json")))]
+    #[error(transparent)] Json(serde_json::Error),
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
     #[error(transparent)] ParseInt(#[from] std::num::ParseIntError),
-    #[cfg(any(feature = "tokio-tungstenite021"))]
-    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio-tungstenite021"))))]
+    #[cfg(feature = "tokio-tungstenite021")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
     #[error(transparent)] Tungstenite021(#[from] tungstenite021::Error),
-    #[cfg(any(feature = "tokio-tungstenite024"))]
-    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio-tungstenite024"))))]
+    #[cfg(feature = "tokio-tungstenite024")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
     #[error(transparent)] Tungstenite024(#[from] tungstenite024::Error),
-    #[cfg(any(feature = "tokio-tungstenite029"))]
-    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio-tungstenite029"))))]
+    #[cfg(feature = "tokio-tungstenite029")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
     #[error(transparent)] Tungstenite029(#[from] tungstenite029::Error),
     #[error(transparent)] Utf8(#[from] std::string::FromUtf8Error),
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// Received a WebSocket message which is not expected at this point, from a [`WebSocketTransport`](crate::WebSocketTransport) which doesn't have a more specific error variant.
+    #[error("unexpected message received from WebSocket: {0}")]
+    UnexpectedWebSocketMessage(String),
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// An error returned by a [`WebSocketTransport`](crate::WebSocketTransport) which doesn't have a more specific error variant.
+    #[error(transparent)] WebSocketTransport(Box<dyn std::error::Error + Send + Sync>),
 }
 
 impl From<Infallible> for ReadErrorKind {
@@ -95,7 +165,7 @@ impl From<String> for ReadErrorKind {
     }
 }
 
-impl<'a> From<&'a str> for ReadErrorKind {
+impl From<&str> for ReadErrorKind {
     fn from(s: &str) -> Self {
         Self::Custom(s.to_owned())
     }
@@ -118,27 +188,42 @@ impl From<ReadErrorKind> for io::Error {
         match e {
             ReadErrorKind::BufSize(e) => io::Error::new(io::ErrorKind::InvalidData, e),
             ReadErrorKind::Io(e) => e,
-            #[cfg(feature = "tokio-tungstenite021")] ReadErrorKind::Tungstenite021(e) => io::Error::new(io::ErrorKind::Other, e),
-            #[cfg(feature = "tokio-tungstenite024")] ReadErrorKind::Tungstenite024(e) => io::Error::new(io::ErrorKind::Other, e),
-            #[cfg(feature = "tokio-tungstenite029")] ReadErrorKind::Tungstenite029(e) => io::Error::new(io::ErrorKind::Other, e),
+            #[cfg(feature = "tokio-tungstenite021")] ReadErrorKind::Tungstenite021(e) => io::Error::other(e),
+            #[cfg(feature = "tokio-tungstenite024")] ReadErrorKind::Tungstenite024(e) => io::Error::other(e),
+            #[cfg(feature = "tokio-tungstenite029")] ReadErrorKind::Tungstenite029(e) => io::Error::other(e),
             ReadErrorKind::Utf8(e) => io::Error::new(io::ErrorKind::InvalidData, e),
             ReadErrorKind::EndOfStream => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+            #[cfg(feature = "websocket")] ReadErrorKind::WebSocketClosed(_) => io::Error::new(io::ErrorKind::UnexpectedEof, e),
             #[cfg(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024"))] ReadErrorKind::WebSocketTextMessage024(ref msg) => io::Error::new(if msg.is_empty() { io::ErrorKind::UnexpectedEof } else { io::ErrorKind::InvalidData }, e),
             #[cfg(feature = "tokio-tungstenite029")] ReadErrorKind::WebSocketTextMessage029(ref msg) => io::Error::new(if msg.is_empty() { io::ErrorKind::UnexpectedEof } else { io::ErrorKind::InvalidData }, e),
+            ReadErrorKind::BitFieldValue(_) |
             ReadErrorKind::FloatNotFinite |
+            ReadErrorKind::InteriorNul |
+            ReadErrorKind::IntOutOfRange { .. } |
+            ReadErrorKind::InvalidChar(_) |
             ReadErrorKind::MaxLen { .. } |
+            ReadErrorKind::TrailingData { .. } |
+            ReadErrorKind::NonCanonical |
+            ReadErrorKind::SystemTimeOutOfRange { .. } |
             ReadErrorKind::UnknownVariant8(_) |
             ReadErrorKind::UnknownVariant16(_) |
             ReadErrorKind::UnknownVariant32(_) |
             ReadErrorKind::UnknownVariant64(_) |
-            ReadErrorKind::UnknownVariant128(_) => io::Error::new(io::ErrorKind::InvalidData, e),
+            ReadErrorKind::UnknownVariant128(_) |
+            ReadErrorKind::VarintOverlong |
+            ReadErrorKind::VarintOverflow { .. } => io::Error::new(io::ErrorKind::InvalidData, e),
             #[cfg(feature = "tokio-tungstenite021")] ReadErrorKind::MessageKind021(_) => io::Error::new(io::ErrorKind::InvalidData, e),
             #[cfg(feature = "tokio-tungstenite024")] ReadErrorKind::MessageKind024(_) => io::Error::new(io::ErrorKind::InvalidData, e),
             #[cfg(feature = "tokio-tungstenite029")] ReadErrorKind::MessageKind029(_) => io::Error::new(io::ErrorKind::InvalidData, e),
-            #[cfg(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024", feature = "tokio-tungstenite029"))] ReadErrorKind::ParseInt(_) => io::Error::new(io::ErrorKind::InvalidData, e),
+            #[cfg(feature = "websocket-json")] ReadErrorKind::Json(e) => e.into(),
+            #[cfg(feature = "websocket")] ReadErrorKind::ParseInt(_) => io::Error::new(io::ErrorKind::InvalidData, e),
+            #[cfg(feature = "websocket")] ReadErrorKind::UnexpectedWebSocketMessage(_) => io::Error::new(io::ErrorKind::InvalidData, e),
+            #[cfg(feature = "websocket")] ReadErrorKind::WebSocketTrailingData { .. } => io::Error::new(io::ErrorKind::InvalidData, e),
+            #[cfg(feature = "websocket")] ReadErrorKind::WebSocketTransport(e) => io::Error::other(e),
+            #[cfg(feature = "digest")] ReadErrorKind::DigestMismatch => io::Error::new(io::ErrorKind::InvalidData, e),
             ReadErrorKind::ReadNever => io::Error::new(io::ErrorKind::InvalidInput, e),
             ReadErrorKind::TryReserve(_) => io::Error::new(io::ErrorKind::OutOfMemory, e),
-            ReadErrorKind::Custom(_) => io::Error::new(io::ErrorKind::Other, e),
+            ReadErrorKind::Custom(_) => io::Error::other(e),
         }
     }
 }
@@ -163,6 +248,11 @@ pub struct ReadError {
 #[derive(Debug, thiserror::Error)]
 #[allow(missing_docs)]
 pub enum WriteErrorKind {
+    /// Attempted to write a value which does not fit into its bit field.
+    #[error("attempted to write a value which does not fit into {bits} bits")]
+    BitFieldOverflow {
+        bits: u32,
+    },
     /// Tried to send a buffer with more than [`u64::MAX`] elements
     #[error("tried to send a buffer with more than u64::MAX elements: {0}")]
     BufSize(#[from] std::num::TryFromIntError),
@@ -170,11 +260,20 @@ pub enum WriteErrorKind {
     #[error("{0}")]
     Custom(String),
     #[error(transparent)] Io(#[from] io::Error),
+    #[cfg(feature = "websocket-json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket-json")))]
+    #[error(transparent)] Json(serde_json::Error),
     #[error("attempted to write length {len} exceeding specified maximum length ({max_len})")]
     MaxLen {
         len: u64,
         max_len: u64,
     },
+    /// Attempted to write an OS string or path which is not valid UTF-8 using a representation which requires UTF-8, see [`Utf8`](crate::Utf8).
+    #[error("attempted to write an OS string or path which is not valid UTF-8")]
+    NonUtf8,
+    /// Attempted to write a [`SystemTime`](std::time::SystemTime) whose distance from the Unix epoch doesn't fit into an [`i64`] number of seconds.
+    #[error("attempted to write an out-of-range system time")]
+    SystemTimeOutOfRange,
     #[cfg(feature = "tokio-tungstenite021")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
     #[error(transparent)] Tungstenite021(#[from] tungstenite021::Error),
@@ -184,6 +283,10 @@ pub enum WriteErrorKind {
     #[cfg(feature = "tokio-tungstenite029")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
     #[error(transparent)] Tungstenite029(#[from] tungstenite029::Error),
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// An error returned by a [`WebSocketTransport`](crate::WebSocketTransport) which doesn't have a more specific error variant.
+    #[error(transparent)] WebSocketTransport(Box<dyn std::error::Error + Send + Sync>),
 }
 
 impl From<Infallible> for WriteErrorKind {
@@ -198,7 +301,7 @@ impl From<String> for WriteErrorKind {
     }
 }
 
-impl<'a> From<&'a str> for WriteErrorKind {
+impl From<&str> for WriteErrorKind {
     fn from(s: &str) -> Self {
         Self::Custom(s.to_owned())
     }
@@ -213,13 +316,18 @@ impl<'a> From<Cow<'a, str>> for WriteErrorKind {
 impl From<WriteErrorKind> for io::Error {
     fn from(e: WriteErrorKind) -> Self {
         match e {
+            WriteErrorKind::BitFieldOverflow { .. } => io::Error::new(io::ErrorKind::InvalidInput, e),
             WriteErrorKind::BufSize(e) => io::Error::new(io::ErrorKind::InvalidData, e),
             WriteErrorKind::Io(e) => e,
+            #[cfg(feature = "websocket-json")] WriteErrorKind::Json(e) => e.into(),
             WriteErrorKind::MaxLen { .. } => io::Error::new(io::ErrorKind::InvalidData, e),
-            #[cfg(feature = "tokio-tungstenite021")] WriteErrorKind::Tungstenite021(e) => io::Error::new(io::ErrorKind::Other, e),
-            #[cfg(feature = "tokio-tungstenite024")] WriteErrorKind::Tungstenite024(e) => io::Error::new(io::ErrorKind::Other, e),
-            #[cfg(feature = "tokio-tungstenite029")] WriteErrorKind::Tungstenite029(e) => io::Error::new(io::ErrorKind::Other, e),
-            WriteErrorKind::Custom(_) => io::Error::new(io::ErrorKind::Other, e),
+            WriteErrorKind::NonUtf8 => io::Error::new(io::ErrorKind::InvalidData, e),
+            WriteErrorKind::SystemTimeOutOfRange => io::Error::new(io::ErrorKind::InvalidInput, e),
+            #[cfg(feature = "tokio-tungstenite021")] WriteErrorKind::Tungstenite021(e) => io::Error::other(e),
+            #[cfg(feature = "tokio-tungstenite024")] WriteErrorKind::Tungstenite024(e) => io::Error::other(e),
+            #[cfg(feature = "tokio-tungstenite029")] WriteErrorKind::Tungstenite029(e) => io::Error::other(e),
+            #[cfg(feature = "websocket")] WriteErrorKind::WebSocketTransport(e) => io::Error::other(e),
+            WriteErrorKind::Custom(_) => io::Error::other(e),
         }
     }
 }
@@ -257,9 +365,9 @@ pub enum ErrorContext {
         /// The context of the error returned from the message's `Protocol` implementation.
         source: Box<Self>,
     },
-    /// The error was produced by a sink returned from [`async_proto::websocket021`](crate::websocket021) and [`async_proto::websocket024`](crate::websocket024).
+    /// The error was produced by a sink returned from one of the `async_proto::websocket*` or `async_proto::accept_websocket*` functions.
     WebSocketSink,
-    /// The error was produced by a stream returned from [`async_proto::websocket021`](crate::websocket021) and [`async_proto::websocket024`](crate::websocket024).
+    /// The error was produced by a stream returned from one of the `async_proto::websocket*` or `async_proto::accept_websocket*` functions.
     WebSocketStream,
     /// The error was produced by the default implementation of a `Protocol` trait method.
     DefaultImpl,
@@ -301,6 +409,13 @@ pub enum ErrorContext {
         /// The context of the error returned from the proxy type's `Protocol` implementation.
         source: Box<Self>,
     },
+    /// The error occurred while converting a field of a type whose `Protocol` implementation was derived with `#[async_proto(bitpacked)]` from/to its bit field representation.
+    BitField,
+    /// The error occurred while reading/writing the presence bitmap of a type whose `Protocol` implementation was derived with `#[async_proto(presence_bitmap)]`.
+    PresenceBitmap {
+        /// The context of the error returned from the bitmap's `Protocol` implementation.
+        source: Box<Self>,
+    },
     /// The error was produced by the `async_proto::bitflags` macro.
     Bitflags {
         /// The context of the error returned from the bits type's `Protocol` implementation.
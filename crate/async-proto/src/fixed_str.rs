@@ -0,0 +1,171 @@
+//! A string type with a fixed-length representation.
+
+use {
+    std::{
+        fmt,
+        future::Future,
+        io::prelude::*,
+        ops::Deref,
+        pin::Pin,
+        str::FromStr,
+    },
+    tokio::io::{
+        AsyncRead,
+        AsyncReadExt as _,
+        AsyncWrite,
+        AsyncWriteExt as _,
+    },
+    crate::{
+        ErrorContext,
+        Protocol,
+        ReadError,
+        ReadErrorKind,
+        WriteError,
+    },
+};
+
+/// The error returned when attempting to construct a [`FixedStr`] from a string which can't be represented.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FixedStrError {
+    /// The string is longer than the fixed length.
+    #[error("string is {len} bytes long but the fixed length is {max_len} bytes")]
+    TooLong {
+        /// The length of the string in bytes.
+        len: usize,
+        /// The fixed length of the `FixedStr` type.
+        max_len: usize,
+    },
+    /// The string contains a NUL byte, which would be indistinguishable from padding.
+    #[error("string contains a NUL byte at byte index {0}")]
+    InteriorNul(usize),
+}
+
+/// A string of up to `N` bytes which is represented as exactly `N` bytes: the string encoded in UTF-8, followed by NUL bytes as padding.
+///
+/// This is useful for speaking existing binary formats with fixed-size string fields. Since NUL bytes are used as padding, the string can't contain them. When reading, the padding must consist only of NUL bytes, otherwise an error ([`ReadErrorKind::InteriorNul`]) is returned.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedStr<const N: usize>(String);
+
+impl<const N: usize> FixedStr<N> {
+    /// Returns an error if the given string is longer than `N` bytes or contains a NUL byte.
+    pub fn new(s: impl Into<String>) -> Result<Self, FixedStrError> {
+        let s = s.into();
+        if s.len() > N {
+            return Err(FixedStrError::TooLong { len: s.len(), max_len: N })
+        }
+        if let Some(idx) = s.bytes().position(|b| b == 0) {
+            return Err(FixedStrError::InteriorNul(idx))
+        }
+        Ok(Self(s))
+    }
+
+    /// Returns the string without the padding.
+    pub fn as_str(&self) -> &str { &self.0 }
+
+    /// Returns the string without the padding.
+    pub fn into_inner(self) -> String { self.0 }
+
+    /// Returns the representation of this string, including the padding.
+    fn to_bytes(&self) -> [u8; N] {
+        let mut buf = [0; N];
+        buf[..self.0.len()].copy_from_slice(self.0.as_bytes());
+        buf
+    }
+
+    /// Parses the representation of a string, including the padding.
+    fn from_bytes(buf: [u8; N]) -> Result<Self, ReadError> {
+        let len = buf.iter().position(|&b| b == 0).unwrap_or(N);
+        if buf[len..].iter().any(|&b| b != 0) {
+            return Err(ReadError {
+                context: ErrorContext::BuiltIn { for_type: "FixedStr" },
+                kind: ReadErrorKind::InteriorNul,
+            })
+        }
+        let mut buf = Vec::from(buf);
+        buf.truncate(len);
+        Ok(Self(String::from_utf8(buf).map_err(|e| ReadError {
+            context: ErrorContext::BuiltIn { for_type: "FixedStr" },
+            kind: e.into(),
+        })?))
+    }
+}
+
+impl<const N: usize> TryFrom<String> for FixedStr<N> {
+    type Error = FixedStrError;
+
+    fn try_from(s: String) -> Result<Self, FixedStrError> {
+        Self::new(s)
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for FixedStr<N> {
+    type Error = FixedStrError;
+
+    fn try_from(s: &str) -> Result<Self, FixedStrError> {
+        Self::new(s)
+    }
+}
+
+impl<const N: usize> FromStr for FixedStr<N> {
+    type Err = FixedStrError;
+
+    fn from_str(s: &str) -> Result<Self, FixedStrError> {
+        Self::new(s)
+    }
+}
+
+impl<const N: usize> From<FixedStr<N>> for String {
+    fn from(FixedStr(s): FixedStr<N>) -> Self {
+        s
+    }
+}
+
+impl<const N: usize> Deref for FixedStr<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str { &self.0 }
+}
+
+impl<const N: usize> fmt::Display for FixedStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<const N: usize> Protocol for FixedStr<N> {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut buf = [0; N];
+            stream.read_exact(&mut buf).await.map_err(|e| ReadError {
+                context: ErrorContext::BuiltIn { for_type: "FixedStr" },
+                kind: e.into(),
+            })?;
+            Self::from_bytes(buf)
+        })
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(async move {
+            sink.write_all(&self.to_bytes()).await.map_err(|e| WriteError {
+                context: ErrorContext::BuiltIn { for_type: "FixedStr" },
+                kind: e.into(),
+            })
+        })
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        let mut buf = [0; N];
+        stream.read_exact(&mut buf).map_err(|e| ReadError {
+            context: ErrorContext::BuiltIn { for_type: "FixedStr" },
+            kind: e.into(),
+        })?;
+        Self::from_bytes(buf)
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        sink.write_all(&self.to_bytes()).map_err(|e| WriteError {
+            context: ErrorContext::BuiltIn { for_type: "FixedStr" },
+            kind: e.into(),
+        })
+    }
+}
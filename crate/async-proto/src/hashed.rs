@@ -0,0 +1,197 @@
+//! Hashing values via their network representation.
+
+use {
+    std::{
+        fmt,
+        future::Future,
+        io::{
+            self,
+            prelude::*,
+        },
+        pin::Pin,
+        task::{
+            Context,
+            Poll,
+            ready,
+        },
+    },
+    digest::{
+        Digest,
+        Output,
+        OutputSizeUser,
+    },
+    tokio::io::{
+        AsyncRead,
+        AsyncReadExt as _,
+        AsyncWrite,
+        AsyncWriteExt as _,
+        ReadBuf,
+    },
+    crate::{
+        ErrorContext,
+        Protocol,
+        ReadError,
+        ReadErrorKind,
+        WriteError,
+    },
+};
+
+/// A sync sink which feeds everything written to it into a hasher.
+pub(crate) struct HashWriter<H>(pub(crate) H);
+
+impl<H: Digest> Write for HashWriter<H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// A stream which feeds everything read from the inner stream into a hasher.
+struct HashReader<'a, R, H> {
+    inner: &'a mut R,
+    hasher: H,
+}
+
+impl<R: Read, H: Digest> Read for HashReader<'_, R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<R: AsyncRead + Unpin, H: Digest + Unpin> AsyncRead for HashReader<'_, R, H> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let prev_len = buf.filled().len();
+        ready!(Pin::new(&mut *self.inner).poll_read(cx, buf))?;
+        self.hasher.update(&buf.filled()[prev_len..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A value along with a digest of its network representation, computed using the hash function `H`.
+///
+/// This is represented as the value followed by the digest. When reading, the digest is computed from the bytes received for the value and compared with the received digest, and a mismatch is an error ([`ReadErrorKind::DigestMismatch`]).
+///
+/// Note that values containing hash-based collections like [`HashMap`](std::collections::HashMap) may be written in a different order after being read, so they should use [`Canonical`](crate::Canonical) or `#[async_proto(canonical)]` if a received `Hashed` value is going to be forwarded.
+pub struct Hashed<T, H: OutputSizeUser> {
+    value: T,
+    digest: Output<H>,
+}
+
+impl<T: Protocol, H: Digest> Hashed<T, H> {
+    /// Computes the digest of the given value.
+    ///
+    /// Returns an error if the value can't be written.
+    pub fn new(value: T) -> Result<Self, WriteError> {
+        let digest = value.hash_with::<H>()?;
+        Ok(Self { value, digest })
+    }
+}
+
+impl<T, H: OutputSizeUser> Hashed<T, H> {
+    /// Returns a reference to the value.
+    pub fn value(&self) -> &T { &self.value }
+
+    /// Returns the digest of the value.
+    pub fn digest(&self) -> &Output<H> { &self.digest }
+
+    /// Returns the value, discarding the digest.
+    pub fn into_inner(self) -> T { self.value }
+
+    /// Returns the value and its digest.
+    pub fn into_parts(self) -> (T, Output<H>) { (self.value, self.digest) }
+}
+
+impl<T: fmt::Debug, H: OutputSizeUser> fmt::Debug for Hashed<T, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hashed")
+            .field("value", &self.value)
+            .field("digest", &self.digest)
+            .finish()
+    }
+}
+
+impl<T: Clone, H: OutputSizeUser> Clone for Hashed<T, H> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            digest: self.digest.clone(),
+        }
+    }
+}
+
+impl<T: PartialEq, H: OutputSizeUser> PartialEq for Hashed<T, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.digest == other.digest && self.value == other.value
+    }
+}
+
+impl<T: Eq, H: OutputSizeUser> Eq for Hashed<T, H> {}
+
+fn digest_mismatch() -> ReadError {
+    ReadError {
+        context: ErrorContext::BuiltIn { for_type: "Hashed" },
+        kind: ReadErrorKind::DigestMismatch,
+    }
+}
+
+impl<T: Protocol + Send + Sync, H: Digest + Send + Unpin> Protocol for Hashed<T, H> {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut reader = HashReader { inner: stream, hasher: H::new() };
+            let value = T::read(&mut reader).await?;
+            let actual = reader.hasher.finalize();
+            let mut digest = Output::<H>::default();
+            stream.read_exact(&mut digest).await.map_err(|e| ReadError {
+                context: ErrorContext::BuiltIn { for_type: "Hashed" },
+                kind: e.into(),
+            })?;
+            if digest != actual { return Err(digest_mismatch()) }
+            Ok(Self { value, digest })
+        })
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(async move {
+            self.value.write(sink).await?;
+            sink.write_all(&self.digest).await.map_err(|e| WriteError {
+                context: ErrorContext::BuiltIn { for_type: "Hashed" },
+                kind: e.into(),
+            })
+        })
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        let mut reader = HashReader { inner: stream, hasher: H::new() };
+        let value = T::read_sync(&mut reader)?;
+        let actual = reader.hasher.finalize();
+        let mut digest = Output::<H>::default();
+        stream.read_exact(&mut digest).map_err(|e| ReadError {
+            context: ErrorContext::BuiltIn { for_type: "Hashed" },
+            kind: e.into(),
+        })?;
+        if digest != actual { return Err(digest_mismatch()) }
+        Ok(Self { value, digest })
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.value.write_sync(sink)?;
+        sink.write_all(&self.digest).map_err(|e| WriteError {
+            context: ErrorContext::BuiltIn { for_type: "Hashed" },
+            kind: e.into(),
+        })
+    }
+}
+
+/// A value along with its SHA-256 digest.
+#[cfg(feature = "sha2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sha2")))]
+pub type Sha256Hashed<T> = Hashed<T, sha2::Sha256>;
+
+/// A value along with its BLAKE3 digest.
+#[cfg(feature = "blake3")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blake3")))]
+pub type Blake3Hashed<T> = Hashed<T, blake3::Hasher>;
@@ -14,6 +14,7 @@ use {
     },
     crate::{
         ErrorContext,
+        LengthPrefix,
         LengthPrefixed,
         Protocol,
         ReadError,
@@ -27,28 +28,28 @@ use {
 #[cfg_attr(docsrs, doc(cfg(feature = "bytes")))]
 impl Protocol for Bytes {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 /// Using [`Bytes`] is recommended for sending large amounts of data, since the [`Protocol`] implementation for `Vec<u8>` reads and writes each byte individually.
 #[cfg_attr(docsrs, doc(cfg(feature = "bytes")))]
 impl LengthPrefixed for Bytes {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = super::read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "bytes::Bytes" }).await?;
+            let len = super::read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "bytes::Bytes" }).await?;
             let mut buf = Vec::default();
             buf.try_resize(len, 0).map_err(|e| ReadError {
                 context: ErrorContext::BuiltIn { for_type: "bytes::Bytes" },
@@ -62,9 +63,9 @@ impl LengthPrefixed for Bytes {
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
-            super::write_len(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "bytes::Bytes" }).await?;
+            super::write_len(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "bytes::Bytes" }).await?;
             sink.write_all(self).await.map_err(|e| WriteError {
                 context: ErrorContext::BuiltIn { for_type: "bytes::Bytes" },
                 kind: e.into(),
@@ -73,8 +74,8 @@ impl LengthPrefixed for Bytes {
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = super::read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "bytes::Bytes" })?;
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = super::read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "bytes::Bytes" })?;
         let mut buf = Vec::default();
         buf.try_resize(len, 0).map_err(|e| ReadError {
             context: ErrorContext::BuiltIn { for_type: "bytes::Bytes" },
@@ -87,8 +88,8 @@ impl LengthPrefixed for Bytes {
         Ok(buf.into())
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        super::write_len_sync(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "bytes::Bytes" })?;
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        super::write_len_sync(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "bytes::Bytes" })?;
         sink.write_all(self).map_err(|e| WriteError {
             context: ErrorContext::BuiltIn { for_type: "bytes::Bytes" },
             kind: e.into(),
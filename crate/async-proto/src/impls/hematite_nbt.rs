@@ -13,6 +13,7 @@ use {
     async_proto_derive::impl_protocol_for,
     crate::{
         ErrorContext,
+        LengthPrefix,
         LengthPrefixed,
         Protocol,
         ReadError,
@@ -26,27 +27,27 @@ use {
 #[cfg_attr(docsrs, doc(cfg(feature = "hematite-nbt")))]
 impl Protocol for nbt::Blob {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 #[cfg_attr(docsrs, doc(cfg(feature = "hematite-nbt")))]
 impl LengthPrefixed for nbt::Blob {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = super::read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "nbt::Blob" }).await?;
+            let len = super::read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "nbt::Blob" }).await?;
             let mut buf = Vec::default();
             buf.try_resize(len, 0).map_err(|e| ReadError {
                 context: ErrorContext::BuiltIn { for_type: "nbt::Blob" },
@@ -63,14 +64,14 @@ impl LengthPrefixed for nbt::Blob {
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
             let mut buf = Vec::default();
             self.to_gzip_writer(&mut buf).map_err(|e| WriteError {
                 context: ErrorContext::BuiltIn { for_type: "nbt::Blob" },
                 kind: WriteErrorKind::Custom(e.to_string()),
             })?;
-            super::write_len(sink, buf.len(), max_len, || ErrorContext::BuiltIn { for_type: "nbt::Blob" }).await?;
+            super::write_len(sink, buf.len(), prefix, || ErrorContext::BuiltIn { for_type: "nbt::Blob" }).await?;
             sink.write_all(&buf).await.map_err(|e| WriteError {
                 context: ErrorContext::BuiltIn { for_type: "nbt::Blob" },
                 kind: e.into(),
@@ -79,8 +80,8 @@ impl LengthPrefixed for nbt::Blob {
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = super::read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "nbt::Blob" })?;
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = super::read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "nbt::Blob" })?;
         let mut buf = Vec::default();
         buf.try_resize(len, 0).map_err(|e| ReadError {
             context: ErrorContext::BuiltIn { for_type: "nbt::Blob" },
@@ -96,13 +97,13 @@ impl LengthPrefixed for nbt::Blob {
         })
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
         let mut buf = Vec::default();
         self.to_gzip_writer(&mut buf).map_err(|e| WriteError {
             context: ErrorContext::BuiltIn { for_type: "nbt::Blob" },
             kind: WriteErrorKind::Custom(e.to_string()),
         })?;
-        super::write_len_sync(sink, buf.len(), max_len, || ErrorContext::BuiltIn { for_type: "nbt::Blob" })?;
+        super::write_len_sync(sink, buf.len(), prefix, || ErrorContext::BuiltIn { for_type: "nbt::Blob" })?;
         sink.write_all(&buf).map_err(|e| WriteError {
             context: ErrorContext::BuiltIn { for_type: "nbt::Blob" },
             kind: e.into(),
@@ -7,24 +7,53 @@ use {
         collections::{
             BTreeMap,
             BTreeSet,
+            BinaryHeap,
             HashMap,
             HashSet,
+            LinkedList,
+            VecDeque,
         },
         convert::{
             TryFrom as _,
             TryInto as _,
         },
+        ffi::{
+            CStr,
+            CString,
+            OsStr,
+            OsString,
+        },
         future::Future,
-        hash::Hash,
+        hash::{
+            BuildHasher,
+            Hash,
+        },
         io::prelude::*,
+        net::{
+            IpAddr,
+            Ipv4Addr,
+            Ipv6Addr,
+            SocketAddr,
+            SocketAddrV4,
+            SocketAddrV6,
+        },
         ops::{
+            Bound,
             Range,
             RangeFrom,
             RangeInclusive,
             RangeTo,
             RangeToInclusive,
         },
+        path::PathBuf,
         pin::Pin,
+        rc::Rc,
+        sync::Arc,
+        time::{
+            Duration,
+            SystemTime,
+            UNIX_EPOCH,
+        },
     },
     byteorder::{
         NetworkEndian,
@@ -43,8 +72,12 @@ use {
     },
     async_proto_derive::impl_protocol_for,
     crate::{
+        Endian,
         ErrorContext,
+        Le,
+        LengthPrefix,
         LengthPrefixed,
+        LengthRepr,
         Protocol,
         ReadError,
         ReadErrorKind,
@@ -52,6 +85,10 @@ use {
         WriteErrorKind,
     },
 };
+#[cfg(unix)] use std::os::unix::ffi::{
+    OsStrExt as _,
+    OsStringExt as _,
+};
 
 #[cfg(feature = "bitvec")] mod bitvec;
 #[cfg(feature = "bytes")] mod bytes;
@@ -74,13 +111,64 @@ use {
 #[cfg(feature = "url")] mod url;
 #[cfg(feature = "uuid")] mod uuid;
 
-async fn read_len<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64, error_ctx: impl Fn() -> ErrorContext) -> Result<usize, ReadError> {
-    let len = match max_len {
-        0 => 0,
-        1..=255 => u8::read(stream).await?.into(),
-        256..=65_535 => u16::read(stream).await?.into(),
-        65_536..=4_294_967_295 => u32::read(stream).await?.into(),
-        _ => u64::read(stream).await?,
+/// Limits the maximum length to what can be represented using the given length representation.
+fn effective_max_len(max_len: u64, repr: LengthRepr) -> u64 {
+    match repr {
+        LengthRepr::U8 => max_len.min(u8::MAX.into()),
+        LengthRepr::U16 => max_len.min(u16::MAX.into()),
+        LengthRepr::U32 => max_len.min(u32::MAX.into()),
+        LengthRepr::Auto | LengthRepr::U64 | LengthRepr::Varint => max_len,
+    }
+}
+
+/// Reads a multi-byte integer length prefix in the given byte order.
+async fn read_len_int<'a, T: Protocol + Send + 'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, endian: Endian) -> Result<T, ReadError> where Le<T>: Protocol {
+    match endian {
+        Endian::Big => T::read(stream).await,
+        Endian::Little => Le::<T>::read(stream).await.map(|Le(len)| len),
+    }
+}
+
+/// Writes a multi-byte integer length prefix in the given byte order.
+async fn write_len_int<'a, T: Protocol + Copy + Sync + 'a, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, len: T, endian: Endian) -> Result<(), WriteError> where Le<T>: Protocol {
+    match endian {
+        Endian::Big => len.write(sink).await,
+        Endian::Little => Le(len).write(sink).await,
+    }
+}
+
+/// Reads a multi-byte integer length prefix in the given byte order.
+fn read_len_int_sync<T: Protocol>(stream: &mut impl Read, endian: Endian) -> Result<T, ReadError> where Le<T>: Protocol {
+    match endian {
+        Endian::Big => T::read_sync(stream),
+        Endian::Little => Le::<T>::read_sync(stream).map(|Le(len)| len),
+    }
+}
+
+/// Writes a multi-byte integer length prefix in the given byte order.
+fn write_len_int_sync<T: Protocol + Copy>(sink: &mut impl Write, len: T, endian: Endian) -> Result<(), WriteError> where Le<T>: Protocol {
+    match endian {
+        Endian::Big => len.write_sync(sink),
+        Endian::Little => Le(len).write_sync(sink),
+    }
+}
+
+pub(crate) async fn read_len<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix, error_ctx: impl Fn() -> ErrorContext) -> Result<usize, ReadError> {
+    let LengthPrefix { max_len, repr, endian } = prefix;
+    let max_len = effective_max_len(max_len, repr);
+    let len = match repr {
+        LengthRepr::Auto => match max_len {
+            0 => 0,
+            1..=255 => u8::read(stream).await?.into(),
+            256..=65_535 => read_len_int::<u16, _>(stream, endian).await?.into(),
+            65_536..=4_294_967_295 => read_len_int::<u32, _>(stream, endian).await?.into(),
+            _ => read_len_int::<u64, _>(stream, endian).await?,
+        },
+        LengthRepr::U8 => u8::read(stream).await?.into(),
+        LengthRepr::U16 => read_len_int::<u16, _>(stream, endian).await?.into(),
+        LengthRepr::U32 => read_len_int::<u32, _>(stream, endian).await?.into(),
+        LengthRepr::U64 => read_len_int::<u64, _>(stream, endian).await?,
+        LengthRepr::Varint => crate::varint::read(stream, u64::BITS, &error_ctx).await? as u64,
     };
     if len > max_len {
         Err(ReadError {
@@ -95,7 +183,9 @@ async fn read_len<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_l
     }
 }
 
-async fn write_len<'a, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, len: usize, max_len: u64, error_ctx: impl Fn() -> ErrorContext) -> Result<(), WriteError> {
+pub(crate) async fn write_len<'a, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, len: usize, prefix: LengthPrefix, error_ctx: impl Fn() -> ErrorContext) -> Result<(), WriteError> {
+    let LengthPrefix { max_len, repr, endian } = prefix;
+    let max_len = effective_max_len(max_len, repr);
     let len = u64::try_from(len).map_err(|e| WriteError {
         context: error_ctx(),
         kind: e.into(),
@@ -106,23 +196,39 @@ async fn write_len<'a, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, len:
             kind: WriteErrorKind::MaxLen { len, max_len },
         })
     }
-    match max_len {
-        0 => {}
-        1..=255 => (len as u8).write(sink).await?,
-        256..=65_535 => (len as u16).write(sink).await?,
-        65_536..=4_294_967_295 => (len as u32).write(sink).await?,
-        _ => len.write(sink).await?,
+    match repr {
+        LengthRepr::Auto => match max_len {
+            0 => {}
+            1..=255 => (len as u8).write(sink).await?,
+            256..=65_535 => write_len_int(sink, len as u16, endian).await?,
+            65_536..=4_294_967_295 => write_len_int(sink, len as u32, endian).await?,
+            _ => write_len_int(sink, len, endian).await?,
+        },
+        LengthRepr::U8 => (len as u8).write(sink).await?,
+        LengthRepr::U16 => write_len_int(sink, len as u16, endian).await?,
+        LengthRepr::U32 => write_len_int(sink, len as u32, endian).await?,
+        LengthRepr::U64 => write_len_int(sink, len, endian).await?,
+        LengthRepr::Varint => crate::varint::write(sink, len.into(), error_ctx).await?,
     }
     Ok(())
 }
 
-fn read_len_sync(stream: &mut impl Read, max_len: u64, error_ctx: impl Fn() -> ErrorContext) -> Result<usize, ReadError> {
-    let len = match max_len {
-        0 => 0,
-        1..=255 => u8::read_sync(stream)?.into(),
-        256..=65_535 => u16::read_sync(stream)?.into(),
-        65_536..=4_294_967_295 => u32::read_sync(stream)?.into(),
-        _ => u64::read_sync(stream)?,
+pub(crate) fn read_len_sync(stream: &mut impl Read, prefix: LengthPrefix, error_ctx: impl Fn() -> ErrorContext) -> Result<usize, ReadError> {
+    let LengthPrefix { max_len, repr, endian } = prefix;
+    let max_len = effective_max_len(max_len, repr);
+    let len = match repr {
+        LengthRepr::Auto => match max_len {
+            0 => 0,
+            1..=255 => u8::read_sync(stream)?.into(),
+            256..=65_535 => read_len_int_sync::<u16>(stream, endian)?.into(),
+            65_536..=4_294_967_295 => read_len_int_sync::<u32>(stream, endian)?.into(),
+            _ => read_len_int_sync::<u64>(stream, endian)?,
+        },
+        LengthRepr::U8 => u8::read_sync(stream)?.into(),
+        LengthRepr::U16 => read_len_int_sync::<u16>(stream, endian)?.into(),
+        LengthRepr::U32 => read_len_int_sync::<u32>(stream, endian)?.into(),
+        LengthRepr::U64 => read_len_int_sync::<u64>(stream, endian)?,
+        LengthRepr::Varint => crate::varint::read_sync(stream, u64::BITS, &error_ctx)? as u64,
     };
     if len > max_len {
         Err(ReadError {
@@ -137,7 +243,9 @@ fn read_len_sync(stream: &mut impl Read, max_len: u64, error_ctx: impl Fn() -> E
     }
 }
 
-fn write_len_sync(sink: &mut impl Write, len: usize, max_len: u64, error_ctx: impl Fn() -> ErrorContext) -> Result<(), WriteError> {
+pub(crate) fn write_len_sync(sink: &mut impl Write, len: usize, prefix: LengthPrefix, error_ctx: impl Fn() -> ErrorContext) -> Result<(), WriteError> {
+    let LengthPrefix { max_len, repr, endian } = prefix;
+    let max_len = effective_max_len(max_len, repr);
     let len = u64::try_from(len).map_err(|e| WriteError {
         context: error_ctx(),
         kind: e.into(),
@@ -148,50 +256,128 @@ fn write_len_sync(sink: &mut impl Write, len: usize, max_len: u64, error_ctx: im
             kind: WriteErrorKind::MaxLen { len, max_len },
         })
     }
-    match max_len {
-        0 => {}
-        1..=255 => (len as u8).write_sync(sink)?,
-        256..=65_535 => (len as u16).write_sync(sink)?,
-        65_536..=4_294_967_295 => (len as u32).write_sync(sink)?,
-        _ => len.write_sync(sink)?,
+    match repr {
+        LengthRepr::Auto => match max_len {
+            0 => {}
+            1..=255 => (len as u8).write_sync(sink)?,
+            256..=65_535 => write_len_int_sync(sink, len as u16, endian)?,
+            65_536..=4_294_967_295 => write_len_int_sync(sink, len as u32, endian)?,
+            _ => write_len_int_sync(sink, len, endian)?,
+        },
+        LengthRepr::U8 => (len as u8).write_sync(sink)?,
+        LengthRepr::U16 => write_len_int_sync(sink, len as u16, endian)?,
+        LengthRepr::U32 => write_len_int_sync(sink, len as u32, endian)?,
+        LengthRepr::U64 => write_len_int_sync(sink, len, endian)?,
+        LengthRepr::Varint => crate::varint::write_sync(sink, len.into(), error_ctx)?,
     }
     Ok(())
 }
 
+async fn write_slice<'a, T: Protocol + Sync, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, slice: &'a [T], prefix: LengthPrefix, for_type: &'static str) -> Result<(), WriteError> {
+    write_len(sink, slice.len(), prefix, || ErrorContext::BuiltIn { for_type }).await?;
+    for elt in slice {
+        elt.write(sink).await?;
+    }
+    Ok(())
+}
+
+fn write_slice_sync<T: Protocol>(sink: &mut impl Write, slice: &[T], prefix: LengthPrefix, for_type: &'static str) -> Result<(), WriteError> {
+    write_len_sync(sink, slice.len(), prefix, || ErrorContext::BuiltIn { for_type })?;
+    for elt in slice {
+        elt.write_sync(sink)?;
+    }
+    Ok(())
+}
+
+async fn write_str<'a, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, s: &'a str, prefix: LengthPrefix, for_type: &'static str) -> Result<(), WriteError> {
+    write_bytes(sink, s.as_bytes(), prefix, for_type).await
+}
+
+fn write_str_sync(sink: &mut impl Write, s: &str, prefix: LengthPrefix, for_type: &'static str) -> Result<(), WriteError> {
+    write_bytes_sync(sink, s.as_bytes(), prefix, for_type)
+}
+
+/// Reads a length-prefixed byte buffer, like the representation of a [`String`] without the UTF-8 check.
+pub(crate) async fn read_bytes<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix, for_type: &'static str) -> Result<Vec<u8>, ReadError> {
+    let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type }).await?;
+    let mut buf = Vec::default();
+    buf.try_resize(len, 0).map_err(|e| ReadError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })?;
+    stream.read_exact(&mut buf).await.map_err(|e| ReadError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })?;
+    Ok(buf)
+}
+
+pub(crate) fn read_bytes_sync(stream: &mut impl Read, prefix: LengthPrefix, for_type: &'static str) -> Result<Vec<u8>, ReadError> {
+    let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type })?;
+    let mut buf = Vec::default();
+    buf.try_resize(len, 0).map_err(|e| ReadError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })?;
+    stream.read_exact(&mut buf).map_err(|e| ReadError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })?;
+    Ok(buf)
+}
+
+pub(crate) async fn write_bytes<'a, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, bytes: &'a [u8], prefix: LengthPrefix, for_type: &'static str) -> Result<(), WriteError> {
+    write_len(sink, bytes.len(), prefix, || ErrorContext::BuiltIn { for_type }).await?;
+    sink.write_all(bytes).await.map_err(|e| WriteError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })?;
+    Ok(())
+}
+
+pub(crate) fn write_bytes_sync(sink: &mut impl Write, bytes: &[u8], prefix: LengthPrefix, for_type: &'static str) -> Result<(), WriteError> {
+    write_len_sync(sink, bytes.len(), prefix, || ErrorContext::BuiltIn { for_type })?;
+    sink.write_all(bytes).map_err(|e| WriteError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })?;
+    Ok(())
+}
+
 macro_rules! impl_protocol_primitive {
     ($ty:ty, $read:ident, $write:ident$(, $endian:ty)?) => {
         /// Primitive number types are encoded in [big-endian](https://en.wikipedia.org/wiki/Big-endian) format.
         impl Protocol for $ty {
             fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
                 Box::pin(async move {
-                    Ok(stream.$read().await.map_err(|e| ReadError {
+                    stream.$read().await.map_err(|e| ReadError {
                         context: ErrorContext::BuiltIn { for_type: stringify!($ty) },
                         kind: e.into(),
-                    })?)
+                    })
                 })
             }
 
             fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
                 Box::pin(async move {
-                    Ok(sink.$write(*self).await.map_err(|e| WriteError {
+                    sink.$write(*self).await.map_err(|e| WriteError {
                         context: ErrorContext::BuiltIn { for_type: stringify!($ty) },
                         kind: e.into(),
-                    })?)
+                    })
                 })
             }
 
             fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-                Ok(stream.$read$(::<$endian>)?().map_err(|e| ReadError {
+                stream.$read$(::<$endian>)?().map_err(|e| ReadError {
                     context: ErrorContext::BuiltIn { for_type: stringify!($ty) },
                     kind: e.into(),
-                })?)
+                })
             }
 
             fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-                Ok(sink.$write$(::<$endian>)?(*self).map_err(|e| WriteError {
+                sink.$write$(::<$endian>)?(*self).map_err(|e| WriteError {
                     context: ErrorContext::BuiltIn { for_type: stringify!($ty) },
                     kind: e.into(),
-                })?)
+                })
             }
         }
     };
@@ -377,10 +563,10 @@ impl Protocol for bool {
 impl<T: Protocol> Protocol for Box<T> {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            Ok(<Box<_> as FallibleBox<_>>::try_new(T::read(stream).await?).map_err(|e| ReadError {
+            <Box<_> as FallibleBox<_>>::try_new(T::read(stream).await?).map_err(|e| ReadError {
                 context: ErrorContext::BuiltIn { for_type: "Box" },
                 kind: e.into(),
-            })?)
+            })
         })
     }
 
@@ -389,10 +575,10 @@ impl<T: Protocol> Protocol for Box<T> {
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Ok(<Box<_> as FallibleBox<_>>::try_new(T::read_sync(stream)?).map_err(|e| ReadError {
+        <Box<_> as FallibleBox<_>>::try_new(T::read_sync(stream)?).map_err(|e| ReadError {
             context: ErrorContext::BuiltIn { for_type: "Box" },
             kind: e.into(),
-        })?)
+        })
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
@@ -400,34 +586,203 @@ impl<T: Protocol> Protocol for Box<T> {
     }
 }
 
+impl<T: LengthPrefixed> LengthPrefixed for Box<T> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            <Box<_> as FallibleBox<_>>::try_new(T::read_length_prefixed(stream, prefix).await?).map_err(|e| ReadError {
+                context: ErrorContext::BuiltIn { for_type: "Box" },
+                kind: e.into(),
+            })
+        })
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        (**self).write_length_prefixed(sink, prefix)
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        <Box<_> as FallibleBox<_>>::try_new(T::read_length_prefixed_sync(stream, prefix)?).map_err(|e| ReadError {
+            context: ErrorContext::BuiltIn { for_type: "Box" },
+            kind: e.into(),
+        })
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        (**self).write_length_prefixed_sync(sink, prefix)
+    }
+}
+
+macro_rules! impl_protocol_shared {
+    ($ptr:ident) => {
+        /// A shared pointer is represented like its value.
+        ///
+        /// The value is read fallibly, but the shared allocation it's moved into aborts if there's not enough memory, since shared pointers don't have a stable fallible constructor yet.
+        impl<T: Protocol> Protocol for $ptr<T> {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    Ok($ptr::new(T::read(stream).await?))
+                })
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                (**self).write(sink)
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                Ok($ptr::new(T::read_sync(stream)?))
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                (**self).write_sync(sink)
+            }
+        }
+
+        /// The value is read fallibly, but the shared allocation it's moved into aborts if there's not enough memory, since shared pointers don't have a stable fallible constructor yet.
+        impl<T: LengthPrefixed> LengthPrefixed for $ptr<T> {
+            fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    Ok($ptr::new(T::read_length_prefixed(stream, prefix).await?))
+                })
+            }
+
+            fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                (**self).write_length_prefixed(sink, prefix)
+            }
+
+            fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+                Ok($ptr::new(T::read_length_prefixed_sync(stream, prefix)?))
+            }
+
+            fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+                (**self).write_length_prefixed_sync(sink, prefix)
+            }
+        }
+    };
+}
+
+impl_protocol_shared!(Arc);
+impl_protocol_shared!(Rc);
+
+macro_rules! impl_protocol_unsized {
+    ($ptr:ident$(, $alloc_doc:literal)?) => {
+        /// A string slice behind a pointer is represented like a [`String`].
+        $(#[doc = ""] #[doc = $alloc_doc])?
+        impl Protocol for $ptr<str> {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Self::read_length_prefixed(stream, LengthPrefix::default())
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                self.write_length_prefixed(sink, LengthPrefix::default())
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                self.write_length_prefixed_sync(sink, LengthPrefix::default())
+            }
+        }
+
+        /// A string slice behind a pointer is represented like a [`String`].
+        $(#[doc = ""] #[doc = $alloc_doc])?
+        impl LengthPrefixed for $ptr<str> {
+            fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    Ok(Self::from(String::read_length_prefixed(stream, prefix).await?))
+                })
+            }
+
+            fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                Box::pin(write_str(sink, self, prefix, concat!(stringify!($ptr), "<str>")))
+            }
+
+            fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+                Ok(Self::from(String::read_length_prefixed_sync(stream, prefix)?))
+            }
+
+            fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+                write_str_sync(sink, self, prefix, concat!(stringify!($ptr), "<str>"))
+            }
+        }
+
+        /// A slice behind a pointer is represented like a [`Vec`].
+        $(#[doc = ""] #[doc = $alloc_doc])?
+        impl<T: Protocol + Send + Sync> Protocol for $ptr<[T]> {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Self::read_length_prefixed(stream, LengthPrefix::default())
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                self.write_length_prefixed(sink, LengthPrefix::default())
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                self.write_length_prefixed_sync(sink, LengthPrefix::default())
+            }
+        }
+
+        /// A slice behind a pointer is represented like a [`Vec`].
+        $(#[doc = ""] #[doc = $alloc_doc])?
+        impl<T: Protocol + Send + Sync> LengthPrefixed for $ptr<[T]> {
+            fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    Ok(Self::from(Vec::read_length_prefixed(stream, prefix).await?))
+                })
+            }
+
+            fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                Box::pin(write_slice(sink, self, prefix, concat!(stringify!($ptr), "<[T]>")))
+            }
+
+            fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+                Ok(Self::from(Vec::read_length_prefixed_sync(stream, prefix)?))
+            }
+
+            fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+                write_slice_sync(sink, self, prefix, concat!(stringify!($ptr), "<[T]>"))
+            }
+        }
+    };
+}
+
+impl_protocol_unsized!(Box);
+impl_protocol_unsized!(Arc, "The contents are read fallibly, but copying them into the shared allocation aborts if there's not enough memory, since shared pointers don't have a stable fallible constructor yet.");
+impl_protocol_unsized!(Rc, "The contents are read fallibly, but copying them into the shared allocation aborts if there's not enough memory, since shared pointers don't have a stable fallible constructor yet.");
+
 /// A vector is prefixed with the length as a [`u64`].
 ///
 /// Note that due to Rust's lack of [specialization](https://github.com/rust-lang/rust/issues/31844), this implementation is inefficient for `Vec<u8>`.
 /// Prefer [`Bytes`](https://docs.rs/bytes/latest/bytes/struct.Bytes.html) if possible.
 impl<T: Protocol + Send + Sync> Protocol for Vec<T> {
         fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 /// Note that due to Rust's lack of [specialization](https://github.com/rust-lang/rust/issues/31844), this implementation is inefficient for `Vec<u8>`.
 /// Prefer [`Bytes`](https://docs.rs/bytes/latest/bytes/struct.Bytes.html) if possible.
 impl<T: Protocol + Send + Sync> LengthPrefixed for Vec<T> {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "Vec" }).await?;
+            let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "Vec" }).await?;
             let mut buf = <Self as FallibleVec<_>>::try_with_capacity(len).map_err(|e| ReadError {
                 context: ErrorContext::BuiltIn { for_type: "Vec" },
                 kind: e.into(),
@@ -439,18 +794,12 @@ impl<T: Protocol + Send + Sync> LengthPrefixed for Vec<T> {
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        Box::pin(async move {
-            write_len(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "Vec" }).await?;
-            for elt in self {
-                elt.write(sink).await?;
-            }
-            Ok(())
-        })
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(write_slice(sink, self, prefix, "Vec"))
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "Vec" })?;
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "Vec" })?;
         let mut buf = <Self as FallibleVec<_>>::try_with_capacity(len).map_err(|e| ReadError {
             context: ErrorContext::BuiltIn { for_type: "Vec" },
             kind: e.into(),
@@ -461,38 +810,92 @@ impl<T: Protocol + Send + Sync> LengthPrefixed for Vec<T> {
         Ok(buf)
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        write_len_sync(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "Vec" })?;
-        for elt in self {
-            elt.write_sync(sink)?;
-        }
-        Ok(())
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_slice_sync(sink, self, prefix, "Vec")
     }
 }
 
+macro_rules! impl_protocol_seq {
+    ($ty:ident $(, $bound:path)?) => {
+        /// This collection is represented like a [`Vec`] of its elements in iteration order.
+        impl<T: Protocol + Send + Sync $(+ $bound)?> Protocol for $ty<T> {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Self::read_length_prefixed(stream, LengthPrefix::default())
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                self.write_length_prefixed(sink, LengthPrefix::default())
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                self.write_length_prefixed_sync(sink, LengthPrefix::default())
+            }
+        }
+
+        /// This collection is represented like a [`Vec`] of its elements in iteration order.
+        impl<T: Protocol + Send + Sync $(+ $bound)?> LengthPrefixed for $ty<T> {
+            fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    Ok(Vec::read_length_prefixed(stream, prefix).await?.into_iter().collect())
+                })
+            }
+
+            fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                Box::pin(async move {
+                    write_len(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: stringify!($ty) }).await?;
+                    for elt in self {
+                        elt.write(sink).await?;
+                    }
+                    Ok(())
+                })
+            }
+
+            fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+                Ok(Vec::read_length_prefixed_sync(stream, prefix)?.into_iter().collect())
+            }
+
+            fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+                write_len_sync(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: stringify!($ty) })?;
+                for elt in self {
+                    elt.write_sync(sink)?;
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_protocol_seq!(VecDeque);
+impl_protocol_seq!(BinaryHeap, Ord);
+impl_protocol_seq!(LinkedList);
+
 /// A set is prefixed with the length as a [`u64`].
 impl<T: Protocol + Ord + Send + Sync + 'static> Protocol for BTreeSet<T> {
         fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 impl<T: Protocol + Ord + Send + Sync + 'static> LengthPrefixed for BTreeSet<T> {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "BTreeSet" }).await?;
+            let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "BTreeSet" }).await?;
             let mut set = Self::default();
             for _ in 0..len {
                 set.insert(T::read(stream).await?); //TODO use fallible allocation once available
@@ -501,9 +904,9 @@ impl<T: Protocol + Ord + Send + Sync + 'static> LengthPrefixed for BTreeSet<T> {
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
-            write_len(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "BTreeSet" }).await?;
+            write_len(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "BTreeSet" }).await?;
             for elt in self {
                 elt.write(sink).await?;
             }
@@ -511,16 +914,16 @@ impl<T: Protocol + Ord + Send + Sync + 'static> LengthPrefixed for BTreeSet<T> {
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "BTreeSet" })?;        let mut set = Self::default();
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "BTreeSet" })?;        let mut set = Self::default();
         for _ in 0..len {
             set.insert(T::read_sync(stream)?); //TODO use fallible allocation once available
         }
         Ok(set)
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        write_len_sync(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "BTreeSet" })?;
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_len_sync(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "BTreeSet" })?;
         for elt in self {
             elt.write_sync(sink)?;
         }
@@ -529,29 +932,29 @@ impl<T: Protocol + Ord + Send + Sync + 'static> LengthPrefixed for BTreeSet<T> {
 }
 
 /// A set is prefixed with the length as a [`u64`].
-impl<T: Protocol + Eq + Hash + Send + Sync> Protocol for HashSet<T> {
+impl<T: Protocol + Eq + Hash + Send + Sync, S: BuildHasher + Default + Send + Sync> Protocol for HashSet<T, S> {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
-impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for HashSet<T> {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+impl<T: Protocol + Eq + Hash + Send + Sync, S: BuildHasher + Default + Send + Sync> LengthPrefixed for HashSet<T, S> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "HashSet" }).await?;
-            let mut set = Self::with_capacity(len); //TODO use fallible allocation once available
+            let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "HashSet" }).await?;
+            let mut set = Self::with_capacity_and_hasher(len, S::default()); //TODO use fallible allocation once available
             for _ in 0..len {
                 set.insert(T::read(stream).await?);
             }
@@ -559,9 +962,9 @@ impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for HashSet<T> {
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
-            write_len(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "HashSet" }).await?;
+            write_len(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "HashSet" }).await?;
             for elt in self {
                 elt.write(sink).await?;
             }
@@ -569,17 +972,17 @@ impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for HashSet<T> {
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "HashSet" })?;
-        let mut set = Self::with_capacity(len); //TODO use fallible allocation once available
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "HashSet" })?;
+        let mut set = Self::with_capacity_and_hasher(len, S::default()); //TODO use fallible allocation once available
         for _ in 0..len {
             set.insert(T::read_sync(stream)?);
         }
         Ok(set)
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        write_len_sync(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "HashSet" })?;
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_len_sync(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "HashSet" })?;
         for elt in self {
             elt.write_sync(sink)?;
         }
@@ -590,103 +993,301 @@ impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for HashSet<T> {
 /// A string is encoded in UTF-8 and prefixed with the length in bytes as a [`u64`].
 impl Protocol for String {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 /// A string is encoded in UTF-8 and prefixed with the length in bytes.
 impl LengthPrefixed for String {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "String" }).await?;
-            let mut buf = Vec::default();
-            buf.try_resize(len, 0).map_err(|e| ReadError {
+            Self::from_utf8(read_bytes(stream, prefix, "String").await?).map_err(|e| ReadError {
                 context: ErrorContext::BuiltIn { for_type: "String" },
                 kind: e.into(),
-            })?;
-            stream.read_exact(&mut buf).await.map_err(|e| ReadError {
-                context: ErrorContext::BuiltIn { for_type: "String" },
-                kind: e.into(),
-            })?;
-            Ok(Self::from_utf8(buf).map_err(|e| ReadError {
-                context: ErrorContext::BuiltIn { for_type: "String" },
-                kind: e.into(),
-            })?)
+            })
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        Box::pin(async move {
-            write_len(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "String" }).await?;
-            sink.write(self.as_bytes()).await.map_err(|e| WriteError {
-                context: ErrorContext::BuiltIn { for_type: "String" },
-                kind: e.into(),
-            })?;
-            Ok(())
-        })
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(write_str(sink, self, prefix, "String"))
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "String" })?;
-        let mut buf = Vec::default();
-        buf.try_resize(len, 0).map_err(|e| ReadError {
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Self::from_utf8(read_bytes_sync(stream, prefix, "String")?).map_err(|e| ReadError {
             context: ErrorContext::BuiltIn { for_type: "String" },
             kind: e.into(),
-        })?;
-        stream.read_exact(&mut buf).map_err(|e| ReadError {
-            context: ErrorContext::BuiltIn { for_type: "String" },
+        })
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_str_sync(sink, self, prefix, "String")
+    }
+}
+
+#[cfg(unix)]
+fn os_string_from_bytes(buf: Vec<u8>, _: &'static str) -> Result<OsString, ReadError> {
+    Ok(OsString::from_vec(buf))
+}
+
+#[cfg(not(unix))]
+fn os_string_from_bytes(buf: Vec<u8>, for_type: &'static str) -> Result<OsString, ReadError> {
+    String::from_utf8(buf).map(OsString::from).map_err(|e| ReadError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })
+}
+
+#[cfg(unix)]
+fn os_str_bytes<'a>(s: &'a OsStr, _: &'static str) -> Result<&'a [u8], WriteError> {
+    Ok(s.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn os_str_bytes<'a>(s: &'a OsStr, for_type: &'static str) -> Result<&'a [u8], WriteError> {
+    s.to_str().map(str::as_bytes).ok_or(WriteError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: WriteErrorKind::NonUtf8,
+    })
+}
+
+macro_rules! impl_protocol_os_string {
+    ($ty:ident) => {
+        /// On Unix, OS strings and paths are represented as their raw bytes, prefixed with the length as a [`u64`]. On other platforms, they are represented like a [`String`] and writing one which is not valid UTF-8 is an error.
+        ///
+        /// Either way, values which are valid UTF-8 are represented like the equivalent [`String`]. The [`Utf8`](crate::Utf8) wrapper can be used to require UTF-8 on all platforms, so values can always be read on other platforms.
+        impl Protocol for $ty {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Self::read_length_prefixed(stream, LengthPrefix::default())
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                self.write_length_prefixed(sink, LengthPrefix::default())
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                self.write_length_prefixed_sync(sink, LengthPrefix::default())
+            }
+        }
+
+        impl LengthPrefixed for $ty {
+            fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    Ok(Self::from(os_string_from_bytes(read_bytes(stream, prefix, stringify!($ty)).await?, stringify!($ty))?))
+                })
+            }
+
+            fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                Box::pin(async move {
+                    write_bytes(sink, os_str_bytes(self.as_ref(), stringify!($ty))?, prefix, stringify!($ty)).await
+                })
+            }
+
+            fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+                Ok(Self::from(os_string_from_bytes(read_bytes_sync(stream, prefix, stringify!($ty))?, stringify!($ty))?))
+            }
+
+            fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+                write_bytes_sync(sink, os_str_bytes(self.as_ref(), stringify!($ty))?, prefix, stringify!($ty))
+            }
+        }
+    };
+}
+
+impl_protocol_os_string!(OsString);
+impl_protocol_os_string!(PathBuf);
+
+async fn read_c_string<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64, for_type: &'static str) -> Result<CString, ReadError> {
+    let mut buf = Vec::default();
+    loop {
+        let byte = stream.read_u8().await.map_err(|e| ReadError {
+            context: ErrorContext::BuiltIn { for_type },
             kind: e.into(),
         })?;
-        Ok(Self::from_utf8(buf).map_err(|e| ReadError {
-            context: ErrorContext::BuiltIn { for_type: "String" },
+        if byte == 0 { break }
+        if buf.len() as u64 >= max_len {
+            return Err(ReadError {
+                context: ErrorContext::BuiltIn { for_type },
+                kind: ReadErrorKind::MaxLen { len: buf.len() as u64 + 1, max_len },
+            })
+        }
+        buf.try_push(byte).map_err(|e| ReadError {
+            context: ErrorContext::BuiltIn { for_type },
             kind: e.into(),
-        })?)
+        })?;
     }
+    Ok(CString::new(buf).expect("reading stops at the first NUL byte"))
+}
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        write_len_sync(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "String" })?;
-        sink.write(self.as_bytes()).map_err(|e| WriteError {
-            context: ErrorContext::BuiltIn { for_type: "String" },
+fn read_c_string_sync(stream: &mut impl Read, max_len: u64, for_type: &'static str) -> Result<CString, ReadError> {
+    let mut buf = Vec::default();
+    loop {
+        let byte = stream.read_u8().map_err(|e| ReadError {
+            context: ErrorContext::BuiltIn { for_type },
             kind: e.into(),
         })?;
+        if byte == 0 { break }
+        if buf.len() as u64 >= max_len {
+            return Err(ReadError {
+                context: ErrorContext::BuiltIn { for_type },
+                kind: ReadErrorKind::MaxLen { len: buf.len() as u64 + 1, max_len },
+            })
+        }
+        buf.try_push(byte).map_err(|e| ReadError {
+            context: ErrorContext::BuiltIn { for_type },
+            kind: e.into(),
+        })?;
+    }
+    Ok(CString::new(buf).expect("reading stops at the first NUL byte"))
+}
+
+fn check_c_string_len(s: &CStr, max_len: u64, for_type: &'static str) -> Result<(), WriteError> {
+    let len = s.count_bytes() as u64;
+    if len > max_len {
+        Err(WriteError {
+            context: ErrorContext::BuiltIn { for_type },
+            kind: WriteErrorKind::MaxLen { len, max_len },
+        })
+    } else {
         Ok(())
     }
 }
 
+async fn write_c_string<'a, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, s: &'a CStr, max_len: u64, for_type: &'static str) -> Result<(), WriteError> {
+    check_c_string_len(s, max_len, for_type)?;
+    sink.write_all(s.to_bytes_with_nul()).await.map_err(|e| WriteError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })
+}
+
+fn write_c_string_sync(sink: &mut impl Write, s: &CStr, max_len: u64, for_type: &'static str) -> Result<(), WriteError> {
+    check_c_string_len(s, max_len, for_type)?;
+    sink.write_all(s.to_bytes_with_nul()).map_err(|e| WriteError {
+        context: ErrorContext::BuiltIn { for_type },
+        kind: e.into(),
+    })
+}
+
+/// A C string is represented as its bytes followed by a NUL byte, without a length prefix.
+///
+/// Since the end of the string can only be detected by reading the NUL byte, reading is done one byte at a time, so the stream should be buffered.
+///
+/// The length is not limited by default. Since the end of the string isn't known in advance, a peer can make the reader allocate arbitrary amounts of memory, so consider limiting the length using the [`LengthPrefixed`] impl (e.g. using the `#[async_proto(max_len = ...)]` attribute when deriving [`Protocol`]).
+impl Protocol for CString {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, LengthPrefix::default())
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, LengthPrefix::default())
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
+    }
+}
+
+/// The maximum length applies to the bytes before the NUL byte. Since there is no length prefix, the [`repr`](LengthPrefix::repr) is ignored.
+impl LengthPrefixed for CString {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(read_c_string(stream, prefix.max_len, "CString"))
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(write_c_string(sink, self, prefix.max_len, "CString"))
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        read_c_string_sync(stream, prefix.max_len, "CString")
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_c_string_sync(sink, self, prefix.max_len, "CString")
+    }
+}
+
+/// Represented like a [`CString`].
+impl Protocol for Box<CStr> {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, LengthPrefix::default())
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, LengthPrefix::default())
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
+    }
+}
+
+/// Represented like a [`CString`].
+impl LengthPrefixed for Box<CStr> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(read_c_string(stream, prefix.max_len, "Box<CStr>").await?.into_boxed_c_str())
+        })
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(write_c_string(sink, self, prefix.max_len, "Box<CStr>"))
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Ok(read_c_string_sync(stream, prefix.max_len, "Box<CStr>")?.into_boxed_c_str())
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_c_string_sync(sink, self, prefix.max_len, "Box<CStr>")
+    }
+}
+
 impl<K: Protocol + Ord + Send + Sync + 'static, V: Protocol + Send + Sync + 'static> Protocol for BTreeMap<K, V> {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 impl<K: Protocol + Ord + Send + Sync + 'static, V: Protocol + Send + Sync + 'static> LengthPrefixed for BTreeMap<K, V> {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "BTreeMap" }).await?;
+            let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "BTreeMap" }).await?;
             let mut map = Self::default();
             for _ in 0..len {
                 map.insert(K::read(stream).await?, V::read(stream).await?); //TODO use fallible allocation once available
@@ -695,9 +1296,9 @@ impl<K: Protocol + Ord + Send + Sync + 'static, V: Protocol + Send + Sync + 'sta
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
-            write_len(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "BTreeMap" }).await?;
+            write_len(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "BTreeMap" }).await?;
             for (k, v) in self {
                 k.write(sink).await?;
                 v.write(sink).await?;
@@ -706,8 +1307,8 @@ impl<K: Protocol + Ord + Send + Sync + 'static, V: Protocol + Send + Sync + 'sta
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "BTreeMap" })?;
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "BTreeMap" })?;
         let mut map = Self::default();
         for _ in 0..len {
             map.insert(K::read_sync(stream)?, V::read_sync(stream)?); //TODO use fallible allocation once available
@@ -715,8 +1316,8 @@ impl<K: Protocol + Ord + Send + Sync + 'static, V: Protocol + Send + Sync + 'sta
         Ok(map)
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        write_len_sync(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "BTreeMap" })?;
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_len_sync(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "BTreeMap" })?;
         for (k, v) in self {
             k.write_sync(sink)?;
             v.write_sync(sink)?;
@@ -726,29 +1327,29 @@ impl<K: Protocol + Ord + Send + Sync + 'static, V: Protocol + Send + Sync + 'sta
 }
 
 /// A map is prefixed with the length as a [`u64`].
-impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> Protocol for HashMap<K, V> {
+impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync, S: BuildHasher + Default + Send + Sync> Protocol for HashMap<K, V, S> {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
-impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> LengthPrefixed for HashMap<K, V> {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync, S: BuildHasher + Default + Send + Sync> LengthPrefixed for HashMap<K, V, S> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "HashMap" }).await?;
-            let mut map = Self::with_capacity(len); //TODO use fallible allocation once available
+            let len = read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "HashMap" }).await?;
+            let mut map = Self::with_capacity_and_hasher(len, S::default()); //TODO use fallible allocation once available
             for _ in 0..len {
                 map.insert(K::read(stream).await?, V::read(stream).await?);
             }
@@ -756,9 +1357,9 @@ impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> LengthPre
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
-            write_len(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "HashMap" }).await?;
+            write_len(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "HashMap" }).await?;
             for (k, v) in self {
                 k.write(sink).await?;
                 v.write(sink).await?;
@@ -767,17 +1368,17 @@ impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> LengthPre
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "HashMap" })?;
-        let mut map = Self::with_capacity(len); //TODO use fallible allocation once available
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "HashMap" })?;
+        let mut map = Self::with_capacity_and_hasher(len, S::default()); //TODO use fallible allocation once available
         for _ in 0..len {
             map.insert(K::read_sync(stream)?, V::read_sync(stream)?);
         }
         Ok(map)
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        write_len_sync(sink, self.len(), max_len, || ErrorContext::BuiltIn { for_type: "HashMap" })?;
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_len_sync(sink, self.len(), prefix, || ErrorContext::BuiltIn { for_type: "HashMap" })?;
         for (k, v) in self {
             k.write_sync(sink)?;
             v.write_sync(sink)?;
@@ -825,30 +1426,30 @@ where B::Owned: Protocol + Send + Sync {
 /// Note that due to a restriction in the type system, writing a borrowed cow requires cloning it.
 impl<'cow, B: ToOwned + Sync + ?Sized> LengthPrefixed for std::borrow::Cow<'cow, B>
 where B::Owned: LengthPrefixed + Send + Sync {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            Ok(Self::Owned(B::Owned::read_length_prefixed(stream, max_len).await?))
+            Ok(Self::Owned(B::Owned::read_length_prefixed(stream, prefix).await?))
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
             match self {
-                Self::Borrowed(borrowed) => (*borrowed).to_owned().write_length_prefixed(sink, max_len).await?,
-                Self::Owned(owned) => owned.write_length_prefixed(sink, max_len).await?,
+                Self::Borrowed(borrowed) => (*borrowed).to_owned().write_length_prefixed(sink, prefix).await?,
+                Self::Owned(owned) => owned.write_length_prefixed(sink, prefix).await?,
             }
             Ok(())
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        Ok(Self::Owned(B::Owned::read_length_prefixed_sync(stream, max_len)?))
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Ok(Self::Owned(B::Owned::read_length_prefixed_sync(stream, prefix)?))
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
         match self {
-            Self::Borrowed(borrowed) => (*borrowed).to_owned().write_length_prefixed_sync(sink, max_len)?,
-            Self::Owned(owned) => owned.write_length_prefixed_sync(sink, max_len)?,
+            Self::Borrowed(borrowed) => (*borrowed).to_owned().write_length_prefixed_sync(sink, prefix)?,
+            Self::Owned(owned) => owned.write_length_prefixed_sync(sink, prefix)?,
         }
         Ok(())
     }
@@ -864,7 +1465,7 @@ impl From<F32Proxy> for f32 {
     }
 }
 
-impl<'a> From<&'a f32> for F32Proxy {
+impl From<&f32> for F32Proxy {
     fn from(val: &f32) -> Self {
         Self(val.to_be_bytes())
     }
@@ -880,12 +1481,66 @@ impl From<F64Proxy> for f64 {
     }
 }
 
-impl<'a> From<&'a f64> for F64Proxy {
+impl From<&f64> for F64Proxy {
     fn from(val: &f64) -> Self {
         Self(val.to_be_bytes())
     }
 }
 
+#[derive(Protocol)]
+#[async_proto(internal)]
+struct UsizeProxy(u64);
+
+impl TryFrom<UsizeProxy> for usize {
+    type Error = ReadErrorKind;
+
+    fn try_from(UsizeProxy(n): UsizeProxy) -> Result<Self, ReadErrorKind> {
+        n.try_into().map_err(|_| ReadErrorKind::IntOutOfRange { for_type: "usize", value: n.into() })
+    }
+}
+
+impl From<&usize> for UsizeProxy {
+    fn from(n: &usize) -> Self {
+        Self(*n as u64)
+    }
+}
+
+#[derive(Protocol)]
+#[async_proto(internal)]
+struct IsizeProxy(i64);
+
+impl TryFrom<IsizeProxy> for isize {
+    type Error = ReadErrorKind;
+
+    fn try_from(IsizeProxy(n): IsizeProxy) -> Result<Self, ReadErrorKind> {
+        n.try_into().map_err(|_| ReadErrorKind::IntOutOfRange { for_type: "isize", value: n.into() })
+    }
+}
+
+impl From<&isize> for IsizeProxy {
+    fn from(n: &isize) -> Self {
+        Self(*n as i64)
+    }
+}
+
+#[derive(Protocol)]
+#[async_proto(internal)]
+struct CharProxy(u32);
+
+impl TryFrom<CharProxy> for char {
+    type Error = ReadErrorKind;
+
+    fn try_from(CharProxy(n): CharProxy) -> Result<Self, ReadErrorKind> {
+        Self::from_u32(n).ok_or(ReadErrorKind::InvalidChar(n))
+    }
+}
+
+impl From<&char> for CharProxy {
+    fn from(c: &char) -> Self {
+        Self(u32::from(*c))
+    }
+}
+
 #[derive(Protocol)]
 #[async_proto(internal)]
 struct DurationProxy {
@@ -893,14 +1548,14 @@ struct DurationProxy {
     subsec_nanos: u32,
 }
 
-impl From<DurationProxy> for std::time::Duration {
+impl From<DurationProxy> for Duration {
     fn from(DurationProxy { secs, subsec_nanos }: DurationProxy) -> Self {
         Self::new(secs, subsec_nanos)
     }
 }
 
-impl<'a> From<&'a std::time::Duration> for DurationProxy {
-    fn from(duration: &std::time::Duration) -> Self {
+impl From<&Duration> for DurationProxy {
+    fn from(duration: &Duration) -> Self {
         Self {
             secs: duration.as_secs(),
             subsec_nanos: duration.subsec_nanos(),
@@ -908,6 +1563,97 @@ impl<'a> From<&'a std::time::Duration> for DurationProxy {
     }
 }
 
+#[derive(Protocol)]
+#[async_proto(internal)]
+struct SocketAddrV4Proxy {
+    ip: Ipv4Addr,
+    port: u16,
+}
+
+impl From<SocketAddrV4Proxy> for SocketAddrV4 {
+    fn from(SocketAddrV4Proxy { ip, port }: SocketAddrV4Proxy) -> Self {
+        Self::new(ip, port)
+    }
+}
+
+impl From<&SocketAddrV4> for SocketAddrV4Proxy {
+    fn from(addr: &SocketAddrV4) -> Self {
+        Self {
+            ip: *addr.ip(),
+            port: addr.port(),
+        }
+    }
+}
+
+#[derive(Protocol)]
+#[async_proto(internal)]
+struct SocketAddrV6Proxy {
+    ip: Ipv6Addr,
+    port: u16,
+    flowinfo: u32,
+    scope_id: u32,
+}
+
+impl From<SocketAddrV6Proxy> for SocketAddrV6 {
+    fn from(SocketAddrV6Proxy { ip, port, flowinfo, scope_id }: SocketAddrV6Proxy) -> Self {
+        Self::new(ip, port, flowinfo, scope_id)
+    }
+}
+
+impl From<&SocketAddrV6> for SocketAddrV6Proxy {
+    fn from(addr: &SocketAddrV6) -> Self {
+        Self {
+            ip: *addr.ip(),
+            port: addr.port(),
+            flowinfo: addr.flowinfo(),
+            scope_id: addr.scope_id(),
+        }
+    }
+}
+
+#[derive(Protocol)]
+#[async_proto(internal)]
+struct SystemTimeProxy {
+    secs: i64,
+    subsec_nanos: u32,
+}
+
+impl TryFrom<SystemTimeProxy> for SystemTime {
+    type Error = ReadErrorKind;
+
+    fn try_from(SystemTimeProxy { secs, subsec_nanos }: SystemTimeProxy) -> Result<Self, ReadErrorKind> {
+        if secs >= 0 {
+            UNIX_EPOCH.checked_add(Duration::from_secs(secs.unsigned_abs()))
+        } else {
+            UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))
+        }
+            .and_then(|time| time.checked_add(Duration::from_nanos(subsec_nanos.into())))
+            .ok_or(ReadErrorKind::SystemTimeOutOfRange { secs, subsec_nanos })
+    }
+}
+
+impl TryFrom<&SystemTime> for SystemTimeProxy {
+    type Error = WriteErrorKind;
+
+    fn try_from(time: &SystemTime) -> Result<Self, WriteErrorKind> {
+        Ok(match time.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => Self {
+                secs: since_epoch.as_secs().try_into().map_err(|_| WriteErrorKind::SystemTimeOutOfRange)?,
+                subsec_nanos: since_epoch.subsec_nanos(),
+            },
+            Err(e) => {
+                let until_epoch = e.duration();
+                let secs = i64::try_from(until_epoch.as_secs()).map_err(|_| WriteErrorKind::SystemTimeOutOfRange)?;
+                if until_epoch.subsec_nanos() == 0 {
+                    Self { secs: -secs, subsec_nanos: 0 }
+                } else {
+                    Self { secs: -secs - 1, subsec_nanos: 1_000_000_000 - until_epoch.subsec_nanos() }
+                }
+            }
+        })
+    }
+}
+
 impl_protocol_for! {
     #[async_proto(attr(doc = "A nonzero integer is represented like its value."))]
     #[async_proto(via = u8, clone, map_err = |_| ReadErrorKind::UnknownVariant8(0))]
@@ -949,6 +1695,18 @@ impl_protocol_for! {
     #[async_proto(via = i128, clone, map_err = |_| ReadErrorKind::UnknownVariant128(0))]
     type std::num::NonZeroI128;
 
+    #[async_proto(attr(doc = "A `usize` is represented as a [`u64`] for portability. Reading a value which doesn't fit into a `usize` on this platform is an error."))]
+    #[async_proto(via = UsizeProxy)]
+    type usize;
+
+    #[async_proto(attr(doc = "An `isize` is represented as an [`i64`] for portability. Reading a value which doesn't fit into an `isize` on this platform is an error."))]
+    #[async_proto(via = IsizeProxy)]
+    type isize;
+
+    #[async_proto(attr(doc = "A `char` is represented as its Unicode scalar value as a [`u32`]. Reading a value which is not a Unicode scalar value is an error."))]
+    #[async_proto(via = CharProxy)]
+    type char;
+
     #[async_proto(attr(doc = "Primitive number types are encoded in [big-endian](https://en.wikipedia.org/wiki/Big-endian) format."))]
     #[async_proto(via = F32Proxy)]
     type f32;
@@ -997,7 +1755,59 @@ impl_protocol_for! {
 
     struct std::ops::RangeFull;
 
+    #[async_proto(attr(doc = "A wrapping integer is represented like its value."))]
+    #[async_proto(where(T: Protocol + Sync))]
+    struct std::num::Wrapping<T>(T);
+
+    #[async_proto(attr(doc = "A saturating integer is represented like its value."))]
+    #[async_proto(where(T: Protocol + Sync))]
+    struct std::num::Saturating<T>(T);
+
+    #[async_proto(attr(doc = "A `Reverse` is represented like its value."))]
+    #[async_proto(where(T: Protocol + Sync))]
+    struct std::cmp::Reverse<T>(T);
+
+    #[async_proto(attr(doc = "An IPv4 address is represented as its 4 octets in network byte order."))]
+    #[async_proto(via = u32, clone)]
+    type Ipv4Addr;
+
+    #[async_proto(attr(doc = "An IPv6 address is represented as its 16 octets in network byte order."))]
+    #[async_proto(via = u128, clone)]
+    type Ipv6Addr;
+
+    #[async_proto(attr(doc = "An IP address is represented as a [`u8`] tag (0 for IPv4, 1 for IPv6) followed by the address."))]
+    enum IpAddr {
+        V4(Ipv4Addr),
+        V6(Ipv6Addr),
+    }
+
+    #[async_proto(attr(doc = "An IPv4 socket address is represented as the IP address followed by the port as a [`u16`]."))]
+    #[async_proto(via = SocketAddrV4Proxy)]
+    type SocketAddrV4;
+
+    #[async_proto(attr(doc = "An IPv6 socket address is represented as the IP address, followed by the port as a [`u16`], the flow info as a [`u32`], and the scope ID as a [`u32`]."))]
+    #[async_proto(via = SocketAddrV6Proxy)]
+    type SocketAddrV6;
+
+    #[async_proto(attr(doc = "A socket address is represented as a [`u8`] tag (0 for IPv4, 1 for IPv6) followed by the address."))]
+    enum SocketAddr {
+        V4(SocketAddrV4),
+        V6(SocketAddrV6),
+    }
+
+    #[async_proto(attr(doc = "A range bound is represented as a [`u8`] tag (0 for `Included`, 1 for `Excluded`, 2 for `Unbounded`) followed by the value, if any."))]
+    #[async_proto(where(T: Protocol + Sync))]
+    enum Bound<T> {
+        Included(T),
+        Excluded(T),
+        Unbounded,
+    }
+
+    #[async_proto(attr(doc = "A system time is represented as the number of whole seconds since the Unix epoch as an [`i64`] (negative for times before the epoch) followed by the number of nanoseconds to add to that as a [`u32`], similar to [`Duration`]."))]
+    #[async_proto(via = SystemTimeProxy)]
+    type SystemTime;
+
     #[async_proto(attr(doc = "A duration is represented as the number of whole seconds as a [`u64`] followed by the number of subsecond nanoseconds as a [`u32`]."))]
     #[async_proto(via = DurationProxy)]
-    type std::time::Duration;
+    type Duration;
 }
@@ -16,6 +16,7 @@ use {
     },
     crate::{
         ErrorContext,
+        LengthPrefix,
         LengthPrefixed,
         Protocol,
         ReadError,
@@ -28,27 +29,27 @@ use {
 #[cfg_attr(docsrs, doc(cfg(feature = "nonempty-collections")))]
 impl<T: Protocol + Send + Sync> Protocol for NEVec<T> {
         fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 #[cfg_attr(docsrs, doc(cfg(feature = "nonempty-collections")))]
 impl<T: Protocol + Send + Sync> LengthPrefixed for NEVec<T> {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = super::read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "NEVec" }).await?;
+            let len = super::read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "NEVec" }).await?;
             let len = NonZero::new(len).ok_or_else(|| ReadError {
                 context: ErrorContext::BuiltIn { for_type: "NEVec" },
                 kind: ReadErrorKind::UnknownVariant64(0),
@@ -61,9 +62,9 @@ impl<T: Protocol + Send + Sync> LengthPrefixed for NEVec<T> {
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
-            super::write_len(sink, self.len().get(), max_len, || ErrorContext::BuiltIn { for_type: "NEVec" }).await?;
+            super::write_len(sink, self.len().get(), prefix, || ErrorContext::BuiltIn { for_type: "NEVec" }).await?;
             for elt in self {
                 elt.write(sink).await?;
             }
@@ -71,8 +72,8 @@ impl<T: Protocol + Send + Sync> LengthPrefixed for NEVec<T> {
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = super::read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "NEVec" })?;
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = super::read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "NEVec" })?;
         let len = NonZero::new(len).ok_or_else(|| ReadError {
             context: ErrorContext::BuiltIn { for_type: "NEVec" },
             kind: ReadErrorKind::UnknownVariant64(0),
@@ -84,8 +85,8 @@ impl<T: Protocol + Send + Sync> LengthPrefixed for NEVec<T> {
         Ok(buf)
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        super::write_len_sync(sink, self.len().get(), max_len, || ErrorContext::BuiltIn { for_type: "NEVec" })?;
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        super::write_len_sync(sink, self.len().get(), prefix, || ErrorContext::BuiltIn { for_type: "NEVec" })?;
         for elt in self {
             elt.write_sync(sink)?;
         }
@@ -96,28 +97,28 @@ impl<T: Protocol + Send + Sync> LengthPrefixed for NEVec<T> {
 #[cfg_attr(docsrs, doc(cfg(feature = "nonempty-collections")))]
 impl<T: Protocol + Eq + Hash + Send + Sync> Protocol for NESet<T> {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 /// A set is prefixed with the length as a [`u64`].
 #[cfg_attr(docsrs, doc(cfg(feature = "nonempty-collections")))]
 impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for NESet<T> {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = super::read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "NESet" }).await?;
+            let len = super::read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "NESet" }).await?;
             let len = NonZero::new(len).ok_or_else(|| ReadError {
                 context: ErrorContext::BuiltIn { for_type: "NESet" },
                 kind: ReadErrorKind::UnknownVariant64(0),
@@ -130,9 +131,9 @@ impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for NESet<T> {
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
-            super::write_len(sink, self.len().get(), max_len, || ErrorContext::BuiltIn { for_type: "NESet" }).await?;
+            super::write_len(sink, self.len().get(), prefix, || ErrorContext::BuiltIn { for_type: "NESet" }).await?;
             for elt in self {
                 elt.write(sink).await?;
             }
@@ -140,8 +141,8 @@ impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for NESet<T> {
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = super::read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "NESet" })?;
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = super::read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "NESet" })?;
         let len = NonZero::new(len).ok_or_else(|| ReadError {
             context: ErrorContext::BuiltIn { for_type: "NESet" },
             kind: ReadErrorKind::UnknownVariant64(0),
@@ -153,8 +154,8 @@ impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for NESet<T> {
         Ok(set)
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        super::write_len_sync(sink, self.len().get(), max_len, || ErrorContext::BuiltIn { for_type: "NESet" })?;
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        super::write_len_sync(sink, self.len().get(), prefix, || ErrorContext::BuiltIn { for_type: "NESet" })?;
         for elt in self {
             elt.write_sync(sink)?;
         }
@@ -166,27 +167,27 @@ impl<T: Protocol + Eq + Hash + Send + Sync> LengthPrefixed for NESet<T> {
 #[cfg_attr(docsrs, doc(cfg(feature = "nonempty-collections")))]
 impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> Protocol for NEMap<K, V> {
     fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Self::read_length_prefixed(stream, u64::MAX)
+        Self::read_length_prefixed(stream, LengthPrefix::default())
     }
 
     fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
-        self.write_length_prefixed(sink, u64::MAX)
+        self.write_length_prefixed(sink, LengthPrefix::default())
     }
 
     fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
-        Self::read_length_prefixed_sync(stream, u64::MAX)
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
     }
 
     fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
-        self.write_length_prefixed_sync(sink, u64::MAX)
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
     }
 }
 
 #[cfg_attr(docsrs, doc(cfg(feature = "nonempty-collections")))]
 impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> LengthPrefixed for NEMap<K, V> {
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let len = super::read_len(stream, max_len, || ErrorContext::BuiltIn { for_type: "NEMap" }).await?;
+            let len = super::read_len(stream, prefix, || ErrorContext::BuiltIn { for_type: "NEMap" }).await?;
             let len = NonZero::new(len).ok_or_else(|| ReadError {
                 context: ErrorContext::BuiltIn { for_type: "NEMap" },
                 kind: ReadErrorKind::UnknownVariant64(0),
@@ -199,9 +200,9 @@ impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> LengthPre
         })
     }
 
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
         Box::pin(async move {
-            super::write_len(sink, self.len().get(), max_len, || ErrorContext::BuiltIn { for_type: "NEMap" }).await?;
+            super::write_len(sink, self.len().get(), prefix, || ErrorContext::BuiltIn { for_type: "NEMap" }).await?;
             for (k, v) in self {
                 k.write(sink).await?;
                 v.write(sink).await?;
@@ -210,8 +211,8 @@ impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> LengthPre
         })
     }
 
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError> {
-        let len = super::read_len_sync(stream, max_len, || ErrorContext::BuiltIn { for_type: "NEMap" })?;
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        let len = super::read_len_sync(stream, prefix, || ErrorContext::BuiltIn { for_type: "NEMap" })?;
         let len = NonZero::new(len).ok_or_else(|| ReadError {
             context: ErrorContext::BuiltIn { for_type: "NEMap" },
             kind: ReadErrorKind::UnknownVariant64(0),
@@ -223,8 +224,8 @@ impl<K: Protocol + Eq + Hash + Send + Sync, V: Protocol + Send + Sync> LengthPre
         Ok(map)
     }
 
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError> {
-        super::write_len_sync(sink, self.len().get(), max_len, || ErrorContext::BuiltIn { for_type: "NEMap" })?;
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        super::write_len_sync(sink, self.len().get(), prefix, || ErrorContext::BuiltIn { for_type: "NEMap" })?;
         for (k, v) in self {
             k.write_sync(sink)?;
             v.write_sync(sink)?;
@@ -9,6 +9,18 @@
 //!
 //! [`Protocol`] can be derived for `enum`s and `struct`s if all fields implement [`Protocol`].
 //!
+//! Primitive number types are encoded in big-endian byte order by default. The [`Le`] and [`Be`] wrapper types, as well as the `#[async_proto(endian = ...)]` attribute when deriving [`Protocol`], can be used to choose the byte order explicitly, e.g. to model existing little-endian binary formats. The byte order of length prefixes is chosen using [`LengthPrefix`]. Integers and the lengths of collections can also be encoded as variable-length integers using the [`Varint`] and [`ZigZag`] wrapper types or the `#[async_proto(varint)]` attribute.
+//!
+//! The maximum length of collections and strings can be limited using the `#[async_proto(max_len = ...)]` attribute when deriving [`Protocol`]. The [`BoundedVec`], [`BoundedString`], and [`BoundedBytes`] types instead make the limit part of the type, so it also applies to nested values like the elements of a [`Vec`].
+//!
+//! For interoperability with C-derived formats, [`CString`](std::ffi::CString) and `Box<CStr>` are represented as NUL-terminated byte strings without a length prefix, and [`FixedStr`] represents a string as a fixed number of bytes padded with NUL bytes.
+//!
+//! The [`Raw`] and [`Lazy`] types can be used to pass values through without decoding them, e.g. to forward payloads in a proxy.
+//!
+//! Hash-based collections like [`HashMap`](std::collections::HashMap) are written in iteration order by default, so equal values may be represented differently. The [`Canonical`] wrapper type and the `#[async_proto(canonical)]` attribute write them sorted by their encoded keys instead, see [`CanonicalEncoding`].
+//!
+//! OS strings and paths are represented as their raw bytes on Unix. The [`Utf8`] wrapper type can be used to represent them as UTF-8 on all platforms instead, so they can be read on any platform.
+//!
 //! # Features
 //!
 //! This crate offers optional dependencies on the following crates to enable [`Protocol`] implementations for some of their types:
@@ -34,11 +46,34 @@
 //! * [`url`](https://docs.rs/url): [`Url`](https://docs.rs/url/latest/url/struct.Url.html)
 //! * [`uuid`](https://docs.rs/uuid): [`Uuid`](https://docs.rs/uuid/latest/uuid/struct.Uuid.html)
 //!
+#![cfg_attr(feature = "digest", doc = "With the `digest` feature flag, values can be hashed via their network representation using [`Protocol::hash_with`] with any hash function implementing the [`Digest`](https://docs.rs/digest/0.11/digest/trait.Digest.html) trait, and sent along with their digest using [`Hashed`].")]
+#![cfg_attr(not(feature = "digest"), doc = "With the `digest` feature flag, values can be hashed via their network representation using `Protocol::hash_with` with any hash function implementing the [`Digest`](https://docs.rs/digest/0.11/digest/trait.Digest.html) trait, and sent along with their digest using `Hashed`.")]
+#![cfg_attr(all(feature = "sha2", feature = "blake3"), doc = "The `sha2` and `blake3` feature flags additionally enable the [`Sha256Hashed`] and [`Blake3Hashed`] aliases using the hash functions from the respective crates.")]
+#![cfg_attr(not(all(feature = "sha2", feature = "blake3")), doc = "The `sha2` and `blake3` feature flags additionally enable the `Sha256Hashed` and `Blake3Hashed` aliases using the hash functions from the respective crates.")]
+//!
 //! Additionally, this crate offers optional dependencies on the `tokio-tungstenite` crate to add convenience methods for reading/writing [`Protocol`] types from/to its websockets. The following versions are supported:
 //!
 //! * The latest release (currently [`tokio-tungstenite` 0.29](https://docs.rs/tokio-tungstenite/0.29), feature flag `tokio-tungstenite029`)
 //! * The version used by [the `master` branch of `rocket_ws` on GitHub](https://github.com/rwf2/Rocket/tree/master/contrib/ws) (currently [`tokio-tungstenite` 0.24](https://docs.rs/tokio-tungstenite/0.24), feature flag `tokio-tungstenite024`)
 //! * The version used by [the latest `rocket_ws` crates.io release](https://docs.rs/rocket_ws) (currently [`tokio-tungstenite` 0.21](https://docs.rs/tokio-tungstenite/0.21), feature flag `tokio-tungstenite021`)
+//!
+#![cfg_attr(feature = "reconnect", doc = "With the `reconnect` feature flag, [`ReconnectingWebSocket`] provides a typed `tokio-tungstenite` 0.29 client which reconnects automatically when the connection is lost.")]
+#![cfg_attr(not(feature = "reconnect"), doc = "With the `reconnect` feature flag, `ReconnectingWebSocket` provides a typed `tokio-tungstenite` 0.29 client which reconnects automatically when the connection is lost.")]
+#![cfg_attr(feature = "tokio-tungstenite029", doc = "For blocking code, the `tokio-tungstenite029` feature flag adds [`connect_sync029`] and [`accept_sync029`], which return a [`SyncWebSocket`].")]
+#![cfg_attr(not(feature = "tokio-tungstenite029"), doc = "For blocking code, the `tokio-tungstenite029` feature flag adds `connect_sync029` and `accept_sync029`, which return a `SyncWebSocket`.")]
+//!
+#![cfg_attr(feature = "websocket", doc = "For `rocket_ws` channels, [`TypedChannel`] works with both `rocket_ws` versions listed above, using the `tokio-tungstenite021` or `tokio-tungstenite024` feature flag respectively.")]
+#![cfg_attr(not(feature = "websocket"), doc = "For `rocket_ws` channels, `TypedChannel` works with both `rocket_ws` versions listed above, using the `tokio-tungstenite021` or `tokio-tungstenite024` feature flag respectively.")]
+//!
+#![cfg_attr(feature = "websocket", doc = "The WebSocket support is built on the [`WebSocketTransport`] trait, which is also implemented for [`async-tungstenite` 0.32](https://docs.rs/async-tungstenite/0.32) (feature flag `async-tungstenite032`).")]
+#![cfg_attr(not(feature = "websocket"), doc = "The WebSocket support is built on the `WebSocketTransport` trait, which is also implemented for [`async-tungstenite` 0.32](https://docs.rs/async-tungstenite/0.32) (feature flag `async-tungstenite032`).")]
+#![cfg_attr(feature = "axum", doc = "The WebSocket support of [`axum` 0.8](https://docs.rs/axum/0.8) is supported as well (feature flag `axum`), see [`TypedWebSocket`].")]
+#![cfg_attr(not(feature = "axum"), doc = "The WebSocket support of [`axum` 0.8](https://docs.rs/axum/0.8) is supported as well (feature flag `axum`), see `TypedWebSocket`.")]
+#![cfg_attr(feature = "websocket-json", doc = "With the `websocket-json` feature flag, values can also be sent as JSON in text messages for debugging, negotiated using a subprotocol (see [`WsFormat`]). This works with any [`WebSocketTransport`], but handshake helpers are only provided for `tokio-tungstenite` 0.29.")]
+#![cfg_attr(not(feature = "websocket-json"), doc = "With the `websocket-json` feature flag, values can also be sent as JSON in text messages for debugging, negotiated using a subprotocol (see `WsFormat`). This works with any `WebSocketTransport`, but handshake helpers are only provided for `tokio-tungstenite` 0.29.")]
+//! Other WebSocket libraries can be supported by implementing it, using the `websocket` feature flag to enable the trait and the generic methods and functions built on it.
+#![cfg_attr(feature = "websocket", doc = "The `keepalive` feature flag allows the typed WebSocket helpers to detect unresponsive peers using pings (see [`WsConfig::keepalive`]). Since this uses tokio's timer, it requires a tokio runtime with the time driver enabled, so it's not enabled by the runtime-agnostic `async-tungstenite032` feature flag.")]
+#![cfg_attr(not(feature = "websocket"), doc = "The `keepalive` feature flag allows the typed WebSocket helpers to detect unresponsive peers using pings (see `WsConfig::keepalive`). Since this uses tokio's timer, it requires a tokio runtime with the time driver enabled, so it's not enabled by the runtime-agnostic `async-tungstenite032` feature flag.")]
 
 use {
     std::{
@@ -54,44 +89,151 @@ use {
         AsyncWrite,
     },
 };
-#[cfg(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024", feature = "tokio-tungstenite029"))] use {
-    std::{
-        iter,
-        mem,
-    },
-    fallible_collections::FallibleVec,
-    futures::{
-        Sink,
-        SinkExt as _,
-        future::{
-            self,
-            Either,
-        },
-        stream::{
-            self,
-            Stream,
-            StreamExt as _,
-            TryStreamExt as _,
-        },
+#[cfg(feature = "websocket")] use futures::{
+    Sink,
+    SinkExt as _,
+    stream::{
+        Stream,
+        TryStreamExt as _,
     },
 };
+#[cfg(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024", feature = "tokio-tungstenite029"))] use futures::stream::StreamExt as _;
 #[cfg(feature = "tokio-tungstenite021")] use tokio_tungstenite021::tungstenite as tungstenite021;
 #[cfg(feature = "tokio-tungstenite024")] use tokio_tungstenite024::tungstenite as tungstenite024;
 #[cfg(feature = "tokio-tungstenite029")] use tokio_tungstenite029::tungstenite as tungstenite029;
+#[cfg(all(feature = "tokio-tungstenite029", feature = "websocket-json"))] use {
+    serde::{
+        Serialize,
+        de::DeserializeOwned,
+    },
+    tungstenite029::{
+        handshake::server::{
+            Request,
+            Response,
+        },
+        http::{
+            HeaderValue,
+            header::SEC_WEBSOCKET_PROTOCOL,
+        },
+    },
+};
 pub use {
     async_proto_derive::{
         Protocol,
         bitflags,
     },
-    crate::error::*,
+    crate::{
+        bitpacked::BitField,
+        bounded::{
+            BoundedBytes,
+            BoundedString,
+            BoundedVec,
+            MaxLenError,
+        },
+        canonical::{
+            Canonical,
+            CanonicalEncoding,
+        },
+        endian::{
+            Be,
+            Endian,
+            Le,
+        },
+        error::*,
+        fixed_str::{
+            FixedStr,
+            FixedStrError,
+        },
+        raw::{
+            Lazy,
+            Raw,
+        },
+        utf8::Utf8,
+        varint::{
+            Varint,
+            ZigZag,
+        },
+    },
+};
+#[cfg(feature = "blake3")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blake3")))]
+pub use crate::hashed::Blake3Hashed;
+#[cfg(feature = "digest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+pub use crate::hashed::Hashed;
+#[cfg(feature = "sha2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sha2")))]
+pub use crate::hashed::Sha256Hashed;
+#[cfg(feature = "websocket")]
+#[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+pub use crate::websocket::{
+    TypedChannel,
+    TypedWsStream,
+    WebSocketTransport,
+    WsCloseFrame,
+    WsConfig,
+    WsKeepalive,
+    WsMessageKind,
+    typed_websocket,
+};
+#[cfg(feature = "async-tungstenite032")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-tungstenite032")))]
+pub use crate::websocket::AsyncTungstenite032;
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+pub use crate::websocket::{
+    Axum,
+    TypedWebSocket,
+};
+#[cfg(feature = "websocket-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "websocket-json")))]
+pub use crate::websocket::{
+    WS_SUBPROTOCOL_BINARY,
+    WS_SUBPROTOCOL_JSON,
+    WsFormat,
+    typed_websocket_json,
+};
+#[cfg(feature = "tokio-tungstenite021")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
+pub use crate::websocket::Tungstenite021;
+#[cfg(feature = "tokio-tungstenite024")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
+pub use crate::websocket::Tungstenite024;
+#[cfg(feature = "tokio-tungstenite029")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
+pub use crate::websocket::{
+    SyncWebSocket,
+    Tungstenite029,
+};
+#[cfg(feature = "reconnect")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reconnect")))]
+pub use crate::reconnect::{
+    ReconnectConfig,
+    ReconnectError,
+    ReconnectEvent,
+    ReconnectingWebSocket,
+};
+#[doc(hidden)] pub use {
+    crate::bitpacked::{ // used in proc macro
+        BitReader,
+        BitWriter,
+    },
+    tokio, // used in proc macro
 };
-#[doc(hidden)] pub use tokio; // used in proc macro
 
+mod bitpacked;
+mod bounded;
+mod canonical;
+mod endian;
 mod error;
+mod fixed_str;
+#[cfg(feature = "digest")] mod hashed;
 mod impls;
-
-/// The maximum message size that can be sent and received by tokio-tungstenite without errors on the default configuration.
-#[cfg(any(feature = "tokio-tungstenite021", feature = "tokio-tungstenite024", feature = "tokio-tungstenite029"))] const WS_MAX_MESSAGE_SIZE: usize = 16777216;
+mod raw;
+#[cfg(feature = "reconnect")] mod reconnect;
+mod utf8;
+mod varint;
+#[cfg(feature = "websocket")] mod websocket;
 
 /// This trait allows reading a value of an implementing type from an async or sync stream, as well as writing one to an async or sync sink.
 pub trait Protocol: Sized {
@@ -115,6 +257,7 @@ pub trait Protocol: Sized {
     /// Takes ownership of an async stream, reads a value of this type from it, then returns it along with the stream.
     ///
     /// This can be used to get around drop glue issues that might arise with `read`.
+    #[allow(clippy::type_complexity)] // spelled out like the other boxed futures in this trait
     fn read_owned<R: AsyncRead + Unpin + Send + 'static>(mut stream: R) -> Pin<Box<dyn Future<Output = Result<(R, Self), ReadError>> + Send>> {
         Box::pin(async move {
             let value = Self::read(&mut stream).await?;
@@ -192,607 +335,242 @@ pub trait Protocol: Sized {
         }
     }
 
-    #[cfg(feature = "tokio-tungstenite021")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
-    /// Reads a value of this type from a `tokio-tungstenite` websocket.
+    #[cfg(feature = "digest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "digest")))]
+    /// Computes a digest of this value's network representation using the hash function `H`.
+    ///
+    /// The result is the same as hashing the bytes written by [`write_sync`](Self::write_sync), but the representation is fed into the hasher as it's being written instead of being collected in a buffer first. See also [`Hashed`], which sends a value along with its digest.
+    fn hash_with<H: digest::Digest>(&self) -> Result<digest::Output<H>, WriteError> {
+        let mut writer = hashed::HashWriter(H::new());
+        self.write_sync(&mut writer)?;
+        Ok(writer.0.finalize())
+    }
+
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// Reads a value of this type from a stream of WebSocket messages, using the given [`WebSocketTransport`] to interpret them.
+    ///
+    /// Chunked messages whose announced length exceeds [`WsConfig::max_message_size`] are rejected before any chunks are read.
     ///
     /// # Cancellation safety
     ///
     /// The default implementation of this method is not cancellation safe.
-    fn read_ws021<'a, R: Stream<Item = Result<tungstenite021::Message, tungstenite021::Error>> + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn read_ws_transport<'a, T: WebSocketTransport, R: Stream<Item = Result<T::Message, T::Error>> + Unpin + Send + 'a>(stream: &'a mut R, config: WsConfig) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
         Box::pin(async move {
-            let packet = stream.try_next().await.map_err(|e| ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?.ok_or_else(|| ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: ReadErrorKind::EndOfStream,
-            })?;
-            match packet {
-                tungstenite021::Message::Text(data) => match data.chars().next() {
-                    Some('m') => {
-                        let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        let mut buf = <Vec<_> as FallibleVec<_>>::try_with_capacity(len).map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        while buf.len() < len {
-                            let packet = stream.try_next().await.map_err(|e| ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: e.into(),
-                            })?.ok_or_else(|| ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::EndOfStream,
-                            })?;
-                            if let tungstenite021::Message::Binary(data) = packet {
-                                buf.extend_from_slice(&data);
-                            } else {
-                                return Err(ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: ReadErrorKind::MessageKind021(packet),
-                                })
-                            }
-                        }
-                        Self::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })
-                    }
-                    _ => Err(ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: ReadErrorKind::WebSocketTextMessage024(data),
-                    }),
-                },
-                tungstenite021::Message::Binary(data) => Self::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                    context: ErrorContext::WebSocket {
-                        source: Box::new(context),
-                    },
-                    kind,
+            match websocket::receive::<T, Self, _>(&mut stream.map_err(T::read_error), config, None, || ErrorContext::DefaultImpl).await? {
+                websocket::Received::Value(value) => Ok(value),
+                websocket::Received::Closed(frame) => Err(ReadError {
+                    context: ErrorContext::DefaultImpl,
+                    kind: ReadErrorKind::WebSocketClosed(frame),
                 }),
-                _ => Err(ReadError {
+                websocket::Received::EndOfStream => Err(ReadError {
                     context: ErrorContext::DefaultImpl,
-                    kind: ReadErrorKind::MessageKind021(packet),
+                    kind: ReadErrorKind::EndOfStream,
                 }),
             }
         })
     }
 
-    #[cfg(feature = "tokio-tungstenite024")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
-    /// Reads a value of this type from a `tokio-tungstenite` websocket.
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// Writes a value of this type to a sink of WebSocket messages, using the given [`WebSocketTransport`] to construct them.
+    ///
+    /// Messages above [`WsConfig::max_message_size`] are rejected and large messages are split into chunks of at most [`WsConfig::max_chunk_size`] bytes.
     ///
     /// # Cancellation safety
     ///
     /// The default implementation of this method is not cancellation safe.
-    fn read_ws024<'a, R: Stream<Item = Result<tungstenite024::Message, tungstenite024::Error>> + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+    fn write_ws_transport<'a, T: WebSocketTransport, W: Sink<T::Message, Error = T::Error> + Unpin + Send + 'a>(&'a self, sink: &'a mut W, config: WsConfig) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>>
+    where Self: Sync {
         Box::pin(async move {
-            let packet = stream.try_next().await.map_err(|e| ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?.ok_or_else(|| ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: ReadErrorKind::EndOfStream,
-            })?;
-            match packet {
-                tungstenite024::Message::Text(data) => match data.chars().next() {
-                    Some('m') => {
-                        let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        let mut buf = <Vec<_> as FallibleVec<_>>::try_with_capacity(len).map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        while buf.len() < len {
-                            let packet = stream.try_next().await.map_err(|e| ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: e.into(),
-                            })?.ok_or_else(|| ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::EndOfStream,
-                            })?;
-                            if let tungstenite024::Message::Binary(data) = packet {
-                                buf.extend_from_slice(&data);
-                            } else {
-                                return Err(ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: ReadErrorKind::MessageKind024(packet),
-                                })
-                            }
-                        }
-                        Self::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })
-                    }
-                    _ => Err(ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: ReadErrorKind::WebSocketTextMessage024(data),
-                    }),
-                },
-                tungstenite024::Message::Binary(data) => Self::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                    context: ErrorContext::WebSocket {
-                        source: Box::new(context),
-                    },
-                    kind,
-                }),
-                _ => Err(ReadError {
+            for msg in websocket::encode::<T>(self, config)? {
+                sink.send(msg).await.map_err(|e| WriteError {
                     context: ErrorContext::DefaultImpl,
-                    kind: ReadErrorKind::MessageKind024(packet),
-                }),
+                    kind: T::write_error(e),
+                })?;
             }
+            Ok(())
         })
     }
 
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// Reads a value of this type from a sync WebSocket, using the given [`WebSocketTransport`] to interpret the messages returned from `read`.
+    ///
+    /// For a `tungstenite` WebSocket, `read` should call its `read` method. Chunked messages whose announced length exceeds [`WsConfig::max_message_size`] are rejected before any chunks are read.
+    fn read_ws_sync_transport<T: WebSocketTransport>(read: impl FnMut() -> Result<T::Message, T::Error>, config: WsConfig) -> Result<Self, ReadError> {
+        websocket::read_sync::<T, _>(read, config)
+    }
+
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    /// Writes a value of this type to a sync WebSocket, using the given [`WebSocketTransport`] to construct the messages passed to `send`.
+    ///
+    /// For a `tungstenite` WebSocket, `send` should call its `send` method, which also flushes the WebSocket. Messages above [`WsConfig::max_message_size`] are rejected and large messages are split into chunks of at most [`WsConfig::max_chunk_size`] bytes.
+    fn write_ws_sync_transport<T: WebSocketTransport>(&self, send: impl FnMut(T::Message) -> Result<(), T::Error>, config: WsConfig) -> Result<(), WriteError> {
+        websocket::write_sync::<T>(self, send, config)
+    }
+
+    #[cfg(feature = "axum")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+    /// Reads a value of this type from an `axum` websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`read_ws_transport`](Self::read_ws_transport) with the [`Axum`] transport.
+    ///
+    /// # Cancellation safety
+    ///
+    /// The default implementation of this method is not cancellation safe.
+    fn read_ws_axum<'a, R: Stream<Item = Result<axum::extract::ws::Message, axum::Error>> + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_ws_transport::<Axum, _>(stream, WsConfig::default())
+    }
+
+    #[cfg(feature = "axum")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+    /// Writes a value of this type to an `axum` websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`write_ws_transport`](Self::write_ws_transport) with the [`Axum`] transport.
+    ///
+    /// # Cancellation safety
+    ///
+    /// The default implementation of this method is not cancellation safe.
+    fn write_ws_axum<'a, W: Sink<axum::extract::ws::Message, Error = axum::Error> + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>>
+    where Self: Sync {
+        self.write_ws_transport::<Axum, _>(sink, WsConfig::default())
+    }
+
+    #[cfg(feature = "tokio-tungstenite021")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
+    /// Reads a value of this type from a `tokio-tungstenite` websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`read_ws_transport`](Self::read_ws_transport) with the [`Tungstenite021`] transport.
+    ///
+    /// # Cancellation safety
+    ///
+    /// The default implementation of this method is not cancellation safe.
+    fn read_ws021<'a, R: Stream<Item = Result<tungstenite021::Message, tungstenite021::Error>> + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_ws_transport::<Tungstenite021, _>(stream, WsConfig::default())
+    }
+
+    #[cfg(feature = "tokio-tungstenite024")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
+    /// Reads a value of this type from a `tokio-tungstenite` websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`read_ws_transport`](Self::read_ws_transport) with the [`Tungstenite024`] transport.
+    ///
+    /// # Cancellation safety
+    ///
+    /// The default implementation of this method is not cancellation safe.
+    fn read_ws024<'a, R: Stream<Item = Result<tungstenite024::Message, tungstenite024::Error>> + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_ws_transport::<Tungstenite024, _>(stream, WsConfig::default())
+    }
+
     #[cfg(feature = "tokio-tungstenite029")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
     /// Reads a value of this type from a `tokio-tungstenite` websocket.
     ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`read_ws_transport`](Self::read_ws_transport) with the [`Tungstenite029`] transport.
+    ///
     /// # Cancellation safety
     ///
     /// The default implementation of this method is not cancellation safe.
     fn read_ws029<'a, R: Stream<Item = Result<tungstenite029::Message, tungstenite029::Error>> + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
-        Box::pin(async move {
-            let packet = stream.try_next().await.map_err(|e| ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?.ok_or_else(|| ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: ReadErrorKind::EndOfStream,
-            })?;
-            match packet {
-                tungstenite029::Message::Text(data) => match data.chars().next() {
-                    Some('m') => {
-                        let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        let mut buf = <Vec<_> as FallibleVec<_>>::try_with_capacity(len).map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        while buf.len() < len {
-                            let packet = stream.try_next().await.map_err(|e| ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: e.into(),
-                            })?.ok_or_else(|| ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::EndOfStream,
-                            })?;
-                            if let tungstenite029::Message::Binary(data) = packet {
-                                buf.extend_from_slice(&data);
-                            } else {
-                                return Err(ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: ReadErrorKind::MessageKind029(packet),
-                                })
-                            }
-                        }
-                        Self::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })
-                    }
-                    _ => Err(ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: ReadErrorKind::WebSocketTextMessage029(data),
-                    }),
-                },
-                tungstenite029::Message::Binary(data) => Self::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                    context: ErrorContext::WebSocket {
-                        source: Box::new(context),
-                    },
-                    kind,
-                }),
-                _ => Err(ReadError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: ReadErrorKind::MessageKind029(packet),
-                }),
-            }
-        })
+        Self::read_ws_transport::<Tungstenite029, _>(stream, WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite021")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
     /// Writes a value of this type to a `tokio-tungstenite` websocket.
     ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`write_ws_transport`](Self::write_ws_transport) with the [`Tungstenite021`] transport.
+    ///
     /// # Cancellation safety
     ///
     /// The default implementation of this method is not cancellation safe.
     fn write_ws021<'a, W: Sink<tungstenite021::Message, Error = tungstenite021::Error> + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>>
     where Self: Sync {
-        Box::pin(async move {
-            let mut buf = Vec::default();
-            self.write_sync(&mut buf).map_err(|WriteError { context, kind }| WriteError {
-                context: ErrorContext::WebSocket {
-                    source: Box::new(context),
-                },
-                kind,
-            })?;
-            if buf.len() <= WS_MAX_MESSAGE_SIZE {
-                sink.send(tungstenite021::Message::binary(buf)).await.map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-            } else {
-                sink.send(tungstenite021::Message::text(format!("m{}", buf.len()))).await.map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-                for chunk in buf.chunks(WS_MAX_MESSAGE_SIZE) {
-                    sink.send(tungstenite021::Message::binary(chunk)).await.map_err(|e| WriteError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                }
-            }
-            Ok(())
-        })
+        self.write_ws_transport::<Tungstenite021, _>(sink, WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite024")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
     /// Writes a value of this type to a `tokio-tungstenite` websocket.
     ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`write_ws_transport`](Self::write_ws_transport) with the [`Tungstenite024`] transport.
+    ///
     /// # Cancellation safety
     ///
     /// The default implementation of this method is not cancellation safe.
     fn write_ws024<'a, W: Sink<tungstenite024::Message, Error = tungstenite024::Error> + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>>
     where Self: Sync {
-        Box::pin(async move {
-            let mut buf = Vec::default();
-            self.write_sync(&mut buf).map_err(|WriteError { context, kind }| WriteError {
-                context: ErrorContext::WebSocket {
-                    source: Box::new(context),
-                },
-                kind,
-            })?;
-            if buf.len() <= WS_MAX_MESSAGE_SIZE {
-                sink.send(tungstenite024::Message::binary(buf)).await.map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-            } else {
-                sink.send(tungstenite024::Message::text(format!("m{}", buf.len()))).await.map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-                for chunk in buf.chunks(WS_MAX_MESSAGE_SIZE) {
-                    sink.send(tungstenite024::Message::binary(chunk)).await.map_err(|e| WriteError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                }
-            }
-            Ok(())
-        })
+        self.write_ws_transport::<Tungstenite024, _>(sink, WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite029")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
     /// Writes a value of this type to a `tokio-tungstenite` websocket.
     ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`write_ws_transport`](Self::write_ws_transport) with the [`Tungstenite029`] transport.
+    ///
     /// # Cancellation safety
     ///
     /// The default implementation of this method is not cancellation safe.
     fn write_ws029<'a, W: Sink<tungstenite029::Message, Error = tungstenite029::Error> + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>>
     where Self: Sync {
-        Box::pin(async move {
-            let mut buf = Vec::default();
-            self.write_sync(&mut buf).map_err(|WriteError { context, kind }| WriteError {
-                context: ErrorContext::WebSocket {
-                    source: Box::new(context),
-                },
-                kind,
-            })?;
-            if buf.len() <= WS_MAX_MESSAGE_SIZE {
-                sink.send(tungstenite029::Message::binary(buf)).await.map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-            } else {
-                sink.send(tungstenite029::Message::text(format!("m{}", buf.len()))).await.map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-                for chunk in buf.chunks(WS_MAX_MESSAGE_SIZE) {
-                    sink.send(tungstenite029::Message::binary(tungstenite029::Bytes::copy_from_slice(chunk))).await.map_err(|e| WriteError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                }
-            }
-            Ok(())
-        })
+        self.write_ws_transport::<Tungstenite029, _>(sink, WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite021")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
     /// Reads a value of this type from a [`tungstenite021`] websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`read_ws_sync_transport`](Self::read_ws_sync_transport) with the [`Tungstenite021`] transport.
     fn read_ws_sync021(websocket: &mut tungstenite021::WebSocket<impl Read + Write>) -> Result<Self, ReadError> {
-        let packet = websocket.read().map_err(|e| ReadError {
-            context: ErrorContext::DefaultImpl,
-            kind: e.into(),
-        })?;
-        match packet {
-            tungstenite021::Message::Text(data) => match data.chars().next() {
-                Some('m') => {
-                    let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                    let mut buf = <Vec<_> as FallibleVec<_>>::try_with_capacity(len).map_err(|e| ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                    while buf.len() < len {
-                        let packet = websocket.read().map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        if let tungstenite021::Message::Binary(data) = packet {
-                            buf.extend_from_slice(&data);
-                        } else {
-                            return Err(ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::MessageKind021(packet),
-                            })
-                        }
-                    }
-                    Self::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                        context: ErrorContext::WebSocket {
-                            source: Box::new(context),
-                        },
-                        kind,
-                    })
-                }
-                _ => return Err(ReadError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: ReadErrorKind::WebSocketTextMessage024(data),
-                }),
-            },
-            tungstenite021::Message::Binary(data) => Self::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                context: ErrorContext::WebSocket {
-                    source: Box::new(context),
-                },
-                kind,
-            }),
-            _ => Err(ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: ReadErrorKind::MessageKind021(packet),
-            }),
-        }
+        Self::read_ws_sync_transport::<Tungstenite021>(|| websocket.read(), WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite024")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
     /// Reads a value of this type from a [`tungstenite024`] websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`read_ws_sync_transport`](Self::read_ws_sync_transport) with the [`Tungstenite024`] transport.
     fn read_ws_sync024(websocket: &mut tungstenite024::WebSocket<impl Read + Write>) -> Result<Self, ReadError> {
-        let packet = websocket.read().map_err(|e| ReadError {
-            context: ErrorContext::DefaultImpl,
-            kind: e.into(),
-        })?;
-        match packet {
-            tungstenite024::Message::Text(data) => match data.chars().next() {
-                Some('m') => {
-                    let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                    let mut buf = <Vec<_> as FallibleVec<_>>::try_with_capacity(len).map_err(|e| ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                    while buf.len() < len {
-                        let packet = websocket.read().map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        if let tungstenite024::Message::Binary(data) = packet {
-                            buf.extend_from_slice(&data);
-                        } else {
-                            return Err(ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::MessageKind024(packet),
-                            })
-                        }
-                    }
-                    Self::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                        context: ErrorContext::WebSocket {
-                            source: Box::new(context),
-                        },
-                        kind,
-                    })
-                }
-                _ => return Err(ReadError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: ReadErrorKind::WebSocketTextMessage024(data),
-                }),
-            },
-            tungstenite024::Message::Binary(data) => Self::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                context: ErrorContext::WebSocket {
-                    source: Box::new(context),
-                },
-                kind,
-            }),
-            _ => Err(ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: ReadErrorKind::MessageKind024(packet),
-            }),
-        }
+        Self::read_ws_sync_transport::<Tungstenite024>(|| websocket.read(), WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite029")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
     /// Reads a value of this type from a [`tungstenite029`] websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`read_ws_sync_transport`](Self::read_ws_sync_transport) with the [`Tungstenite029`] transport.
     fn read_ws_sync029(websocket: &mut tungstenite029::WebSocket<impl Read + Write>) -> Result<Self, ReadError> {
-        let packet = websocket.read().map_err(|e| ReadError {
-            context: ErrorContext::DefaultImpl,
-            kind: e.into(),
-        })?;
-        match packet {
-            tungstenite029::Message::Text(data) => match data.chars().next() {
-                Some('m') => {
-                    let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                    let mut buf = <Vec<_> as FallibleVec<_>>::try_with_capacity(len).map_err(|e| ReadError {
-                        context: ErrorContext::DefaultImpl,
-                        kind: e.into(),
-                    })?;
-                    while buf.len() < len {
-                        let packet = websocket.read().map_err(|e| ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: e.into(),
-                        })?;
-                        if let tungstenite029::Message::Binary(data) = packet {
-                            buf.extend_from_slice(&data);
-                        } else {
-                            return Err(ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::MessageKind029(packet),
-                            })
-                        }
-                    }
-                    Self::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                        context: ErrorContext::WebSocket {
-                            source: Box::new(context),
-                        },
-                        kind,
-                    })
-                }
-                _ => return Err(ReadError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: ReadErrorKind::WebSocketTextMessage029(data),
-                }),
-            },
-            tungstenite029::Message::Binary(data) => Self::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                context: ErrorContext::WebSocket {
-                    source: Box::new(context),
-                },
-                kind,
-            }),
-            _ => Err(ReadError {
-                context: ErrorContext::DefaultImpl,
-                kind: ReadErrorKind::MessageKind029(packet),
-            }),
-        }
+        Self::read_ws_sync_transport::<Tungstenite029>(|| websocket.read(), WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite021")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
     /// Writes a value of this type to a [`tungstenite021`] websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`write_ws_sync_transport`](Self::write_ws_sync_transport) with the [`Tungstenite021`] transport.
     fn write_ws_sync021(&self, websocket: &mut tungstenite021::WebSocket<impl Read + Write>) -> Result<(), WriteError> {
-        let mut buf = Vec::default();
-        self.write_sync(&mut buf).map_err(|WriteError { context, kind }| WriteError {
-            context: ErrorContext::WebSocket {
-                source: Box::new(context),
-            },
-            kind,
-        })?;
-        if buf.len() <= WS_MAX_MESSAGE_SIZE {
-            websocket.send(tungstenite021::Message::binary(buf)).map_err(|e| WriteError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?;
-        } else {
-            websocket.send(tungstenite021::Message::text(format!("m{}", buf.len()))).map_err(|e| WriteError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?;
-            for chunk in buf.chunks(WS_MAX_MESSAGE_SIZE) {
-                websocket.send(tungstenite021::Message::binary(chunk)).map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-            }
-        }
-        websocket.flush().map_err(|e| WriteError {
-            context: ErrorContext::DefaultImpl,
-            kind: e.into(),
-        })?;
-        Ok(())
+        self.write_ws_sync_transport::<Tungstenite021>(|msg| websocket.send(msg), WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite024")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
     /// Writes a value of this type to a [`tungstenite024`] websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`write_ws_sync_transport`](Self::write_ws_sync_transport) with the [`Tungstenite024`] transport.
     fn write_ws_sync024(&self, websocket: &mut tungstenite024::WebSocket<impl Read + Write>) -> Result<(), WriteError> {
-        let mut buf = Vec::default();
-        self.write_sync(&mut buf).map_err(|WriteError { context, kind }| WriteError {
-            context: ErrorContext::WebSocket {
-                source: Box::new(context),
-            },
-            kind,
-        })?;
-        if buf.len() <= WS_MAX_MESSAGE_SIZE {
-            websocket.send(tungstenite024::Message::binary(buf)).map_err(|e| WriteError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?;
-        } else {
-            websocket.send(tungstenite024::Message::text(format!("m{}", buf.len()))).map_err(|e| WriteError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?;
-            for chunk in buf.chunks(WS_MAX_MESSAGE_SIZE) {
-                websocket.send(tungstenite024::Message::binary(chunk)).map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-            }
-        }
-        websocket.flush().map_err(|e| WriteError {
-            context: ErrorContext::DefaultImpl,
-            kind: e.into(),
-        })?;
-        Ok(())
+        self.write_ws_sync_transport::<Tungstenite024>(|msg| websocket.send(msg), WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite029")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
     /// Writes a value of this type to a [`tungstenite029`] websocket.
+    ///
+    /// This uses the [default](WsConfig::default) limits on message sizes. To configure them, use [`write_ws_sync_transport`](Self::write_ws_sync_transport) with the [`Tungstenite029`] transport.
     fn write_ws_sync029(&self, websocket: &mut tungstenite029::WebSocket<impl Read + Write>) -> Result<(), WriteError> {
-        let mut buf = Vec::default();
-        self.write_sync(&mut buf).map_err(|WriteError { context, kind }| WriteError {
-            context: ErrorContext::WebSocket {
-                source: Box::new(context),
-            },
-            kind,
-        })?;
-        if buf.len() <= WS_MAX_MESSAGE_SIZE {
-            websocket.send(tungstenite029::Message::binary(buf)).map_err(|e| WriteError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?;
-        } else {
-            websocket.send(tungstenite029::Message::text(format!("m{}", buf.len()))).map_err(|e| WriteError {
-                context: ErrorContext::DefaultImpl,
-                kind: e.into(),
-            })?;
-            for chunk in buf.chunks(WS_MAX_MESSAGE_SIZE) {
-                websocket.send(tungstenite029::Message::binary(tungstenite029::Bytes::copy_from_slice(chunk))).map_err(|e| WriteError {
-                    context: ErrorContext::DefaultImpl,
-                    kind: e.into(),
-                })?;
-            }
-        }
-        websocket.flush().map_err(|e| WriteError {
-            context: ErrorContext::DefaultImpl,
-            kind: e.into(),
-        })?;
-        Ok(())
+        self.write_ws_sync_transport::<Tungstenite029>(|msg| websocket.send(msg), WsConfig::default())
     }
 
     #[cfg(feature = "tokio-tungstenite021")]
@@ -800,6 +578,7 @@ pub trait Protocol: Sized {
     /// Takes ownership of an async websocket stream, reads a value of this type from it, then returns it along with the stream.
     ///
     /// This can be used to get around drop glue issues that might arise with `read_ws`.
+    #[allow(clippy::type_complexity)] // spelled out like the other boxed futures in this trait
     fn read_ws_owned021<R: Stream<Item = Result<tungstenite021::Message, tungstenite021::Error>> + Unpin + Send + 'static>(mut stream: R) -> Pin<Box<dyn Future<Output = Result<(R, Self), ReadError>> + Send>> {
         Box::pin(async move {
             let value = Self::read_ws021(&mut stream).await?;
@@ -812,6 +591,7 @@ pub trait Protocol: Sized {
     /// Takes ownership of an async websocket stream, reads a value of this type from it, then returns it along with the stream.
     ///
     /// This can be used to get around drop glue issues that might arise with `read_ws`.
+    #[allow(clippy::type_complexity)] // spelled out like the other boxed futures in this trait
     fn read_ws_owned024<R: Stream<Item = Result<tungstenite024::Message, tungstenite024::Error>> + Unpin + Send + 'static>(mut stream: R) -> Pin<Box<dyn Future<Output = Result<(R, Self), ReadError>> + Send>> {
         Box::pin(async move {
             let value = Self::read_ws024(&mut stream).await?;
@@ -824,6 +604,7 @@ pub trait Protocol: Sized {
     /// Takes ownership of an async websocket stream, reads a value of this type from it, then returns it along with the stream.
     ///
     /// This can be used to get around drop glue issues that might arise with `read_ws`.
+    #[allow(clippy::type_complexity)] // spelled out like the other boxed futures in this trait
     fn read_ws_owned029<R: Stream<Item = Result<tungstenite029::Message, tungstenite029::Error>> + Unpin + Send + 'static>(mut stream: R) -> Pin<Box<dyn Future<Output = Result<(R, Self), ReadError>> + Send>> {
         Box::pin(async move {
             let value = Self::read_ws029(&mut stream).await?;
@@ -834,308 +615,212 @@ pub trait Protocol: Sized {
 
 /// This trait allows restricting the acceptable length of collection types.
 ///
-/// By default, types from this crate implementing this trait represent their length as a `u64`. If the maximum length is limited (e.g. using the `#[async_proto(max_len = ...)]` attribute when deriving [`Protocol`]), the length may be represented using a smaller integer type.
+/// By default, types from this crate implementing this trait represent their length as a `u64`. If the maximum length is limited (e.g. using the `#[async_proto(max_len = ...)]` attribute when deriving [`Protocol`]), the length may be represented using a smaller integer type. Alternatively, the representation can be chosen explicitly (e.g. using the `#[async_proto(len = ...)]` attribute), see [`LengthPrefix`].
 pub trait LengthPrefixed: Protocol {
-    /// Reads a value of this type from an async stream, limiting the length to the given value.
-    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, max_len: u64) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>>;
-    /// Writes a value of this type to an async sink, limiting the length to the given value.
-    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, max_len: u64) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>>;
-    /// Reads a value of this type from a sync stream, limiting the length to the given value.
-    fn read_length_prefixed_sync(stream: &mut impl Read, max_len: u64) -> Result<Self, ReadError>;
-    /// Writes a value of this type to a sync sink, limiting the length to the given value.
-    fn write_length_prefixed_sync(&self, sink: &mut impl Write, max_len: u64) -> Result<(), WriteError>;
+    /// Reads a value of this type from an async stream, with the length limited and represented as specified.
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>>;
+    /// Writes a value of this type to an async sink, with the length limited and represented as specified.
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>>;
+    /// Reads a value of this type from a sync stream, with the length limited and represented as specified.
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError>;
+    /// Writes a value of this type to a sync sink, with the length limited and represented as specified.
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError>;
+}
+
+/// Specifies the maximum length of a [`LengthPrefixed`] value and how its length is represented.
+///
+/// The maximum length and the representation can be chosen independently, e.g. to enforce a limit of 200 elements while representing the length as a [`u16`] for compatibility with an existing protocol. If the representation can't encode lengths up to [`max_len`](Self::max_len), the maximum length is further limited accordingly.
+///
+/// The [`Default`] value allows any length up to [`u64::MAX`] and represents it as a big-endian [`u64`], which is the representation used by the [`Protocol`] impls of the types from this crate implementing [`LengthPrefixed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LengthPrefix {
+    /// Values longer than this are rejected, both when reading and when writing.
+    pub max_len: u64,
+    /// How the length is represented.
+    pub repr: LengthRepr,
+    /// The byte order of the length if it's represented as an integer wider than a byte. Ignored for [`LengthRepr::Varint`].
+    pub endian: Endian,
+}
+
+impl Default for LengthPrefix {
+    fn default() -> Self {
+        Self {
+            max_len: u64::MAX,
+            repr: LengthRepr::default(),
+            endian: Endian::default(),
+        }
+    }
+}
+
+impl From<u64> for LengthPrefix {
+    /// Limits the length to the given value, with the default representation.
+    fn from(max_len: u64) -> Self {
+        Self {
+            max_len,
+            repr: LengthRepr::default(),
+            endian: Endian::default(),
+        }
+    }
+}
+
+/// How the length of a [`LengthPrefixed`] value is represented, see [`LengthPrefix`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthRepr {
+    /// The smallest unsigned integer type which can represent [`max_len`](LengthPrefix::max_len), or no length prefix at all if the maximum length is 0.
+    #[default]
+    Auto,
+    /// A [`u8`]. Lengths above [`u8::MAX`] are rejected.
+    U8,
+    /// A [`u16`]. Lengths above [`u16::MAX`] are rejected.
+    U16,
+    /// A [`u32`]. Lengths above [`u32::MAX`] are rejected.
+    U32,
+    /// A [`u64`].
+    U64,
+    /// A [`Varint`], so short lengths take up less space regardless of the maximum length.
+    Varint,
 }
 
 /// Establishes a WebSocket connection to the given URL and returns a typed sink/stream pair.
 ///
 /// Useful for WebSocket connections where the message type per direction is always the same.
+///
+/// `Ping` and `Pong` messages are handled transparently. See [`TypedWsStream`] for how the end of the connection is reported.
+///
+/// This uses the [default](WsConfig::default) limits on message sizes. To configure them, establish the connection using `tokio_tungstenite::connect_async` and pass the halves of the split connection to [`typed_websocket`] with the [`Tungstenite021`] transport.
 #[cfg(feature = "tokio-tungstenite021")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
-pub async fn websocket021<R: Protocol, W: Protocol>(request: impl tungstenite021::client::IntoClientRequest + Unpin) -> tungstenite021::Result<(impl Sink<W, Error = WriteError>, impl Stream<Item = Result<R, ReadError>>)> {
+pub async fn websocket021<R: Protocol, W: Protocol>(request: impl tungstenite021::client::IntoClientRequest + Unpin) -> tungstenite021::Result<(impl Sink<W, Error = WriteError>, TypedWsStream<impl Stream<Item = Result<R, ReadError>>>)> {
     let (sock, _) = tokio_tungstenite021::connect_async(request).await?;
     let (sink, stream) = sock.split();
-    Ok((
-        sink.sink_map_err(|e| WriteError {
-            context: ErrorContext::WebSocketSink,
-            kind: e.into(),
-        }).with_flat_map::<W, _, _>(|msg| {
-            let mut buf = Vec::default();
-            match msg.write_sync(&mut buf) {
-                Ok(()) => Either::Left(if buf.len() <= WS_MAX_MESSAGE_SIZE {
-                    Either::Left(stream::once(future::ready(tungstenite021::Message::binary(buf))))
-                } else {
-                    Either::Right(stream::iter(
-                        iter::once(tungstenite021::Message::text(format!("m{}", buf.len())))
-                        .chain(buf.chunks(WS_MAX_MESSAGE_SIZE).map(tungstenite021::Message::binary))
-                        .collect::<Vec<_>>()
-                    ))
-                }.map(Ok)),
-                Err(WriteError { context, kind }) => Either::Right(stream::once(future::err(WriteError {
-                    context: ErrorContext::WebSocket {
-                        source: Box::new(context),
-                    },
-                    kind,
-                }))),
-            }
-        }),
-        stream.scan(None, |state, res| {
-            fn scanner<R: Protocol>(state: &mut Option<(usize, Vec<u8>)>, res: tungstenite021::Result<tungstenite021::Message>) -> Result<impl Stream<Item = Result<R, ReadError>> + use<R>, ReadError> {
-                let packet = res.map_err(|e| ReadError {
-                    context: ErrorContext::WebSocketStream,
-                    kind: e.into(),
-                })?;
-                Ok(if let Some((len, buf)) = state {
-                    if let tungstenite021::Message::Binary(data) = packet {
-                        buf.extend_from_slice(&data);
-                    } else {
-                        return Err(ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: ReadErrorKind::MessageKind021(packet),
-                        })
-                    }
-                    if buf.len() >= *len {
-                        let buf = mem::take(buf);
-                        *state = None;
-                        Either::Right(stream::once(future::ok(R::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })?)))
-                    } else {
-                        Either::Left(stream::empty())
-                    }
-                } else {
-                    match packet {
-                        tungstenite021::Message::Text(data) => match data.chars().next() {
-                            Some('m') => {
-                                let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: e.into(),
-                                })?;
-                                let buf = FallibleVec::try_with_capacity(len).map_err(|e| ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: e.into(),
-                                })?;
-                                *state = Some((len, buf));
-                                Either::Left(stream::empty())
-                            }
-                            _ => return Err(ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::WebSocketTextMessage024(data),
-                            }),
-                        },
-                        tungstenite021::Message::Binary(data) => Either::Right(stream::once(future::ok(R::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })?))),
-                        _ => return Err(ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: ReadErrorKind::MessageKind021(packet),
-                        }),
-                    }
-                })
-            }
+    Ok(typed_websocket::<Tungstenite021, _, _>(sink, stream, WsConfig::default()))
+}
 
-            future::ready(Some(scanner(state, res)))
-        }).try_flatten(),
-    ))
+/// Performs the server side of a WebSocket handshake on the given stream (e.g. a `TcpStream` returned from a listener) and returns a typed sink/stream pair.
+///
+/// This is the server-side counterpart to [`websocket021`] and uses the same representation of messages, including the chunking of large messages.
+///
+/// This uses the [default](WsConfig::default) limits on message sizes. To configure them, perform the handshake using `tokio_tungstenite::accept_async` and pass the halves of the split connection to [`typed_websocket`] with the [`Tungstenite021`] transport.
+#[cfg(feature = "tokio-tungstenite021")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
+pub async fn accept_websocket021<R: Protocol, W: Protocol>(stream: impl AsyncRead + AsyncWrite + Unpin + Send) -> tungstenite021::Result<(impl Sink<W, Error = WriteError>, TypedWsStream<impl Stream<Item = Result<R, ReadError>>>)> {
+    let sock = tokio_tungstenite021::accept_async(stream).await?;
+    let (sink, stream) = sock.split();
+    Ok(typed_websocket::<Tungstenite021, _, _>(sink, stream, WsConfig::default()))
 }
 
 /// Establishes a WebSocket connection to the given URL and returns a typed sink/stream pair.
 ///
 /// Useful for WebSocket connections where the message type per direction is always the same.
+///
+/// `Ping` and `Pong` messages are handled transparently. See [`TypedWsStream`] for how the end of the connection is reported.
+///
+/// This uses the [default](WsConfig::default) limits on message sizes. To configure them, establish the connection using `tokio_tungstenite::connect_async` and pass the halves of the split connection to [`typed_websocket`] with the [`Tungstenite024`] transport.
 #[cfg(feature = "tokio-tungstenite024")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
-pub async fn websocket024<R: Protocol, W: Protocol>(request: impl tungstenite024::client::IntoClientRequest + Unpin) -> tungstenite024::Result<(impl Sink<W, Error = WriteError>, impl Stream<Item = Result<R, ReadError>>)> {
+pub async fn websocket024<R: Protocol, W: Protocol>(request: impl tungstenite024::client::IntoClientRequest + Unpin) -> tungstenite024::Result<(impl Sink<W, Error = WriteError>, TypedWsStream<impl Stream<Item = Result<R, ReadError>>>)> {
     let (sock, _) = tokio_tungstenite024::connect_async(request).await?;
     let (sink, stream) = sock.split();
-    Ok((
-        sink.sink_map_err(|e| WriteError {
-            context: ErrorContext::WebSocketSink,
-            kind: e.into(),
-        }).with_flat_map::<W, _, _>(|msg| {
-            let mut buf = Vec::default();
-            match msg.write_sync(&mut buf) {
-                Ok(()) => Either::Left(if buf.len() <= WS_MAX_MESSAGE_SIZE {
-                    Either::Left(stream::once(future::ready(tungstenite024::Message::binary(buf))))
-                } else {
-                    Either::Right(stream::iter(
-                        iter::once(tungstenite024::Message::text(format!("m{}", buf.len())))
-                        .chain(buf.chunks(WS_MAX_MESSAGE_SIZE).map(tungstenite024::Message::binary))
-                        .collect::<Vec<_>>()
-                    ))
-                }.map(Ok)),
-                Err(WriteError { context, kind }) => Either::Right(stream::once(future::err(WriteError {
-                    context: ErrorContext::WebSocket {
-                        source: Box::new(context),
-                    },
-                    kind,
-                }))),
-            }
-        }),
-        stream.scan(None, |state, res| {
-            fn scanner<R: Protocol>(state: &mut Option<(usize, Vec<u8>)>, res: tungstenite024::Result<tungstenite024::Message>) -> Result<impl Stream<Item = Result<R, ReadError>> + use<R>, ReadError> {
-                let packet = res.map_err(|e| ReadError {
-                    context: ErrorContext::WebSocketStream,
-                    kind: e.into(),
-                })?;
-                Ok(if let Some((len, buf)) = state {
-                    if let tungstenite024::Message::Binary(data) = packet {
-                        buf.extend_from_slice(&data);
-                    } else {
-                        return Err(ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: ReadErrorKind::MessageKind024(packet),
-                        })
-                    }
-                    if buf.len() >= *len {
-                        let buf = mem::take(buf);
-                        *state = None;
-                        Either::Right(stream::once(future::ok(R::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })?)))
-                    } else {
-                        Either::Left(stream::empty())
-                    }
-                } else {
-                    match packet {
-                        tungstenite024::Message::Text(data) => match data.chars().next() {
-                            Some('m') => {
-                                let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: e.into(),
-                                })?;
-                                let buf = FallibleVec::try_with_capacity(len).map_err(|e| ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: e.into(),
-                                })?;
-                                *state = Some((len, buf));
-                                Either::Left(stream::empty())
-                            }
-                            _ => return Err(ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::WebSocketTextMessage024(data),
-                            }),
-                        },
-                        tungstenite024::Message::Binary(data) => Either::Right(stream::once(future::ok(R::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })?))),
-                        _ => return Err(ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: ReadErrorKind::MessageKind024(packet),
-                        }),
-                    }
-                })
-            }
+    Ok(typed_websocket::<Tungstenite024, _, _>(sink, stream, WsConfig::default()))
+}
 
-            future::ready(Some(scanner(state, res)))
-        }).try_flatten(),
-    ))
+/// Performs the server side of a WebSocket handshake on the given stream (e.g. a `TcpStream` returned from a listener) and returns a typed sink/stream pair.
+///
+/// This is the server-side counterpart to [`websocket024`] and uses the same representation of messages, including the chunking of large messages.
+///
+/// This uses the [default](WsConfig::default) limits on message sizes. To configure them, perform the handshake using `tokio_tungstenite::accept_async` and pass the halves of the split connection to [`typed_websocket`] with the [`Tungstenite024`] transport.
+#[cfg(feature = "tokio-tungstenite024")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
+pub async fn accept_websocket024<R: Protocol, W: Protocol>(stream: impl AsyncRead + AsyncWrite + Unpin + Send) -> tungstenite024::Result<(impl Sink<W, Error = WriteError>, TypedWsStream<impl Stream<Item = Result<R, ReadError>>>)> {
+    let sock = tokio_tungstenite024::accept_async(stream).await?;
+    let (sink, stream) = sock.split();
+    Ok(typed_websocket::<Tungstenite024, _, _>(sink, stream, WsConfig::default()))
 }
 
 /// Establishes a WebSocket connection to the given URL and returns a typed sink/stream pair.
 ///
 /// Useful for WebSocket connections where the message type per direction is always the same.
+///
+/// `Ping` and `Pong` messages are handled transparently. See [`TypedWsStream`] for how the end of the connection is reported.
+///
+/// This uses the [default](WsConfig::default) limits on message sizes. To configure them, establish the connection using `tokio_tungstenite::connect_async` and pass the halves of the split connection to [`typed_websocket`] with the [`Tungstenite029`] transport.
 #[cfg(feature = "tokio-tungstenite029")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
-pub async fn websocket029<R: Protocol, W: Protocol>(request: impl tungstenite029::client::IntoClientRequest + Unpin) -> tungstenite029::Result<(impl Sink<W, Error = WriteError>, impl Stream<Item = Result<R, ReadError>>)> {
+pub async fn websocket029<R: Protocol, W: Protocol>(request: impl tungstenite029::client::IntoClientRequest + Unpin) -> tungstenite029::Result<(impl Sink<W, Error = WriteError>, TypedWsStream<impl Stream<Item = Result<R, ReadError>>>)> {
     let (sock, _) = tokio_tungstenite029::connect_async(request).await?;
     let (sink, stream) = sock.split();
-    Ok((
-        sink.sink_map_err(|e| WriteError {
-            context: ErrorContext::WebSocketSink,
-            kind: e.into(),
-        }).with_flat_map::<W, _, _>(|msg| {
-            let mut buf = Vec::default();
-            match msg.write_sync(&mut buf) {
-                Ok(()) => Either::Left(if buf.len() <= WS_MAX_MESSAGE_SIZE {
-                    Either::Left(stream::once(future::ready(tungstenite029::Message::binary(buf))))
-                } else {
-                    Either::Right(stream::iter(
-                        iter::once(tungstenite029::Message::text(format!("m{}", buf.len())))
-                        .chain(buf.chunks(WS_MAX_MESSAGE_SIZE).map(|chunk| tungstenite029::Message::binary(tungstenite029::Bytes::copy_from_slice(chunk))))
-                        .collect::<Vec<_>>()
-                    ))
-                }.map(Ok)),
-                Err(WriteError { context, kind }) => Either::Right(stream::once(future::err(WriteError {
-                    context: ErrorContext::WebSocket {
-                        source: Box::new(context),
-                    },
-                    kind,
-                }))),
-            }
-        }),
-        stream.scan(None, |state, res| {
-            fn scanner<R: Protocol>(state: &mut Option<(usize, Vec<u8>)>, res: tungstenite029::Result<tungstenite029::Message>) -> Result<impl Stream<Item = Result<R, ReadError>> + use<R>, ReadError> {
-                let packet = res.map_err(|e| ReadError {
-                    context: ErrorContext::WebSocketStream,
-                    kind: e.into(),
-                })?;
-                Ok(if let Some((len, buf)) = state {
-                    if let tungstenite029::Message::Binary(data) = packet {
-                        buf.extend_from_slice(&data);
-                    } else {
-                        return Err(ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: ReadErrorKind::MessageKind029(packet),
-                        })
-                    }
-                    if buf.len() >= *len {
-                        let buf = mem::take(buf);
-                        *state = None;
-                        Either::Right(stream::once(future::ok(R::read_sync(&mut &*buf).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })?)))
-                    } else {
-                        Either::Left(stream::empty())
-                    }
-                } else {
-                    match packet {
-                        tungstenite029::Message::Text(data) => match data.chars().next() {
-                            Some('m') => {
-                                let len = data[1..].parse::<usize>().map_err(|e| ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: e.into(),
-                                })?;
-                                let buf = FallibleVec::try_with_capacity(len).map_err(|e| ReadError {
-                                    context: ErrorContext::DefaultImpl,
-                                    kind: e.into(),
-                                })?;
-                                *state = Some((len, buf));
-                                Either::Left(stream::empty())
-                            }
-                            _ => return Err(ReadError {
-                                context: ErrorContext::DefaultImpl,
-                                kind: ReadErrorKind::WebSocketTextMessage029(data),
-                            }),
-                        },
-                        tungstenite029::Message::Binary(data) => Either::Right(stream::once(future::ok(R::read_sync(&mut &*data).map_err(|ReadError { context, kind }| ReadError {
-                            context: ErrorContext::WebSocket {
-                                source: Box::new(context),
-                            },
-                            kind,
-                        })?))),
-                        _ => return Err(ReadError {
-                            context: ErrorContext::DefaultImpl,
-                            kind: ReadErrorKind::MessageKind029(packet),
-                        }),
-                    }
-                })
-            }
+    Ok(typed_websocket::<Tungstenite029, _, _>(sink, stream, WsConfig::default()))
+}
+
+/// Performs the server side of a WebSocket handshake on the given stream (e.g. a `TcpStream` returned from a listener) and returns a typed sink/stream pair.
+///
+/// This is the server-side counterpart to [`websocket029`] and uses the same representation of messages, including the chunking of large messages.
+///
+/// This uses the [default](WsConfig::default) limits on message sizes. To configure them, perform the handshake using `tokio_tungstenite::accept_async` and pass the halves of the split connection to [`typed_websocket`] with the [`Tungstenite029`] transport.
+#[cfg(feature = "tokio-tungstenite029")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
+pub async fn accept_websocket029<R: Protocol, W: Protocol>(stream: impl AsyncRead + AsyncWrite + Unpin + Send) -> tungstenite029::Result<(impl Sink<W, Error = WriteError>, TypedWsStream<impl Stream<Item = Result<R, ReadError>>>)> {
+    let sock = tokio_tungstenite029::accept_async(stream).await?;
+    let (sink, stream) = sock.split();
+    Ok(typed_websocket::<Tungstenite029, _, _>(sink, stream, WsConfig::default()))
+}
 
-            future::ready(Some(scanner(state, res)))
-        }).try_flatten(),
-    ))
+/// Connects to a WebSocket server using a blocking connection and returns a typed handle for sending and receiving values.
+///
+/// This is the sync counterpart to [`websocket029`] and uses the same representation of messages, including the chunking of large messages.
+///
+/// This uses the [default](WsConfig::default) limits on message sizes. To configure them, connect using `tungstenite::connect` and wrap the WebSocket using [`SyncWebSocket::with_config`].
+#[cfg(feature = "tokio-tungstenite029")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
+pub fn connect_sync029<R: Protocol, W: Protocol>(request: impl tungstenite029::client::IntoClientRequest) -> tungstenite029::Result<SyncWebSocket<R, W>> {
+    let (sock, _) = tungstenite029::connect(request)?;
+    Ok(SyncWebSocket::new(sock))
+}
+
+/// Performs the server side of a WebSocket handshake on the given blocking stream (e.g. a [`TcpStream`](std::net::TcpStream) returned from a listener) and returns a typed handle for sending and receiving values.
+///
+/// This is the sync counterpart to [`accept_websocket029`] and uses the same representation of messages, including the chunking of large messages.
+///
+/// This uses the [default](WsConfig::default) limits on message sizes. To configure them, perform the handshake using `tungstenite::accept` and wrap the WebSocket using [`SyncWebSocket::with_config`].
+#[cfg(feature = "tokio-tungstenite029")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
+pub fn accept_sync029<R: Protocol, W: Protocol, S: Read + Write>(stream: S) -> tungstenite029::Result<SyncWebSocket<R, W, S>> {
+    let sock = tungstenite029::accept(stream).map_err(|e| match e {
+        tungstenite029::HandshakeError::Failure(e) => e,
+        // only occurs on non-blocking streams
+        tungstenite029::HandshakeError::Interrupted(_) => tungstenite029::Error::Io(io::ErrorKind::WouldBlock.into()),
+    })?;
+    Ok(SyncWebSocket::new(sock))
+}
+
+/// Same as [`websocket029`], but with the given configuration, requests the given format using a subprotocol and additionally accepts values in the [JSON format](WsFormat::Json).
+///
+/// The server must select one of the subprotocols offered, as [`accept_websocket_json029`] does, otherwise the handshake fails. If it selects [`WS_SUBPROTOCOL_BINARY`], values are sent in the binary format.
+#[cfg(all(feature = "tokio-tungstenite029", feature = "websocket-json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "tokio-tungstenite029", feature = "websocket-json"))))]
+pub async fn websocket_json029<R: Protocol + DeserializeOwned, W: Protocol + Serialize>(request: impl tungstenite029::client::IntoClientRequest + Unpin, format: WsFormat, config: WsConfig) -> tungstenite029::Result<(impl Sink<W, Error = WriteError>, TypedWsStream<impl Stream<Item = Result<R, ReadError>>>)> {
+    let mut request = request.into_client_request()?;
+    request.headers_mut().insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(format.client_header()));
+    let (sock, response) = tokio_tungstenite029::connect_async(request).await?;
+    let format = WsFormat::from_subprotocol(response.headers().get(SEC_WEBSOCKET_PROTOCOL).and_then(|subprotocol| subprotocol.to_str().ok()));
+    let (sink, stream) = sock.split();
+    Ok(typed_websocket_json::<Tungstenite029, _, _>(sink, stream, format, config))
+}
+
+/// Same as [`accept_websocket029`], but with the given configuration, uses the [JSON format](WsFormat::Json) if the client requests it using a subprotocol, and additionally accepts values in the JSON format.
+///
+/// Clients which don't request a subprotocol, such as those using [`websocket029`], get the binary format.
+#[cfg(all(feature = "tokio-tungstenite029", feature = "websocket-json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "tokio-tungstenite029", feature = "websocket-json"))))]
+pub async fn accept_websocket_json029<R: Protocol + DeserializeOwned, W: Protocol + Serialize>(stream: impl AsyncRead + AsyncWrite + Unpin + Send, config: WsConfig) -> tungstenite029::Result<(impl Sink<W, Error = WriteError>, TypedWsStream<impl Stream<Item = Result<R, ReadError>>>)> {
+    let mut format = WsFormat::Binary;
+    let sock = tokio_tungstenite029::accept_hdr_async(stream, |request: &Request, mut response: Response| {
+        if let Some(negotiated) = request.headers().get(SEC_WEBSOCKET_PROTOCOL).and_then(|offered| offered.to_str().ok()).and_then(WsFormat::negotiate) {
+            format = negotiated;
+            response.headers_mut().insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(negotiated.subprotocol()));
+        }
+        Ok(response)
+    }).await?;
+    let (sink, stream) = sock.split();
+    Ok(typed_websocket_json::<Tungstenite029, _, _>(sink, stream, format, config))
 }
@@ -0,0 +1,204 @@
+//! Types for passing encoded values through without decoding them.
+
+use {
+    std::{
+        fmt,
+        future::Future,
+        io::prelude::*,
+        marker::PhantomData,
+        pin::Pin,
+    },
+    tokio::io::{
+        AsyncRead,
+        AsyncWrite,
+    },
+    crate::{
+        ErrorContext,
+        LengthPrefix,
+        LengthPrefixed,
+        Protocol,
+        ReadError,
+        ReadErrorKind,
+        WriteError,
+        impls::{
+            read_bytes,
+            read_bytes_sync,
+            write_bytes,
+            write_bytes_sync,
+        },
+    },
+};
+
+/// An opaque byte buffer, represented as the bytes prefixed with the length as a [`u64`].
+///
+/// This is represented like a `Vec<u8>`, but read and written as a single buffer rather than byte by byte. A [`Lazy<T>`] can be read as a `Raw` and vice versa, so e.g. a router can forward payloads without knowing their types.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Raw(pub Vec<u8>);
+
+impl From<Vec<u8>> for Raw {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Raw> for Vec<u8> {
+    fn from(Raw(bytes): Raw) -> Self {
+        bytes
+    }
+}
+
+impl<T> From<Lazy<T>> for Raw {
+    fn from(lazy: Lazy<T>) -> Self {
+        Self(lazy.bytes)
+    }
+}
+
+impl Protocol for Raw {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, LengthPrefix::default())
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, LengthPrefix::default())
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
+    }
+}
+
+impl LengthPrefixed for Raw {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(Self(read_bytes(stream, prefix, "Raw").await?))
+        })
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(write_bytes(sink, &self.0, prefix, "Raw"))
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Ok(Self(read_bytes_sync(stream, prefix, "Raw")?))
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_bytes_sync(sink, &self.0, prefix, "Raw")
+    }
+}
+
+/// The encoded representation of a `T`, which is only decoded on demand.
+///
+/// This is represented like a [`Raw`] containing the representation of the `T`. Reading a `Lazy<T>` doesn't decode the value, and writing one writes the stored bytes as they were received, so e.g. a proxy can forward a payload without decoding and re-encoding it. Use [`get`](Self::get) to decode the value.
+pub struct Lazy<T> {
+    bytes: Vec<u8>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Lazy<T> {
+    /// Wraps the given bytes without checking whether they're a valid representation of a `T`.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { bytes, _marker: PhantomData }
+    }
+
+    /// Returns the stored representation.
+    pub fn as_bytes(&self) -> &[u8] { &self.bytes }
+
+    /// Returns the stored representation.
+    pub fn into_bytes(self) -> Vec<u8> { self.bytes }
+}
+
+impl<T: Protocol> Lazy<T> {
+    /// Encodes the given value.
+    pub fn new(value: &T) -> Result<Self, WriteError> {
+        let mut bytes = Vec::default();
+        value.write_sync(&mut bytes)?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// Decodes the stored representation.
+    ///
+    /// Returns an error if the representation is invalid, including if it has any bytes left over after the value.
+    pub fn get(&self) -> Result<T, ReadError> {
+        let mut buf = &*self.bytes;
+        let value = T::read_sync(&mut buf)?;
+        if !buf.is_empty() {
+            return Err(ReadError {
+                context: ErrorContext::BuiltIn { for_type: "Lazy" },
+                kind: ReadErrorKind::TrailingData { len: self.bytes.len() as u64, read: (self.bytes.len() - buf.len()) as u64 },
+            })
+        }
+        Ok(value)
+    }
+}
+
+impl<T> From<Raw> for Lazy<T> {
+    /// Does not check whether the bytes are a valid representation of a `T`.
+    fn from(Raw(bytes): Raw) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl<T> fmt::Debug for Lazy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Lazy").field(&self.bytes).finish()
+    }
+}
+
+impl<T> Clone for Lazy<T> {
+    fn clone(&self) -> Self {
+        Self::from_bytes(self.bytes.clone())
+    }
+}
+
+impl<T> PartialEq for Lazy<T> {
+    /// Compares the stored representations.
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<T> Eq for Lazy<T> {}
+
+impl<T> Protocol for Lazy<T> {
+    fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Self::read_length_prefixed(stream, LengthPrefix::default())
+    }
+
+    fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        self.write_length_prefixed(sink, LengthPrefix::default())
+    }
+
+    fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+        Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+    }
+
+    fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+        self.write_length_prefixed_sync(sink, LengthPrefix::default())
+    }
+}
+
+/// The length prefix applies to the stored representation, in bytes.
+impl<T> LengthPrefixed for Lazy<T> {
+    fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(Self::from_bytes(read_bytes(stream, prefix, "Lazy").await?))
+        })
+    }
+
+    fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+        Box::pin(write_bytes(sink, &self.bytes, prefix, "Lazy"))
+    }
+
+    fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+        Ok(Self::from_bytes(read_bytes_sync(stream, prefix, "Lazy")?))
+    }
+
+    fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+        write_bytes_sync(sink, &self.bytes, prefix, "Lazy")
+    }
+}
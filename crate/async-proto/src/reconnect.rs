@@ -0,0 +1,204 @@
+//! A typed WebSocket client which reconnects automatically.
+
+use {
+    std::{
+        pin::Pin,
+        task::{
+            Context,
+            Poll,
+        },
+        time::Duration,
+    },
+    futures::{
+        SinkExt as _,
+        channel::mpsc,
+        future,
+        stream::{
+            self,
+            Stream,
+            StreamExt as _,
+        },
+    },
+    tokio_tungstenite029::tungstenite::{
+        self as tungstenite029,
+        client::IntoClientRequest,
+    },
+    crate::{
+        Protocol,
+        ReadError,
+        ReadErrorKind,
+        Tungstenite029,
+        WriteError,
+        WriteErrorKind,
+        WsConfig,
+        typed_websocket,
+    },
+};
+
+/// Configures the behavior of a [`ReconnectingWebSocket`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReconnectConfig {
+    /// Configures each connection.
+    pub ws: WsConfig,
+    /// How long to wait before the first attempt to reconnect after the connection was lost. Defaults to 500 milliseconds.
+    ///
+    /// The delay doubles after each failed attempt, up to [`max_backoff`](Self::max_backoff), and is reset once a connection is established.
+    pub initial_backoff: Duration,
+    /// The maximum delay between attempts to reconnect. Defaults to 30 seconds.
+    pub max_backoff: Duration,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            ws: WsConfig::default(),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// An error reported by a [`ReconnectingWebSocket`].
+#[derive(Debug, thiserror::Error)]
+pub enum ReconnectError {
+    /// Establishing a connection failed.
+    #[error("failed to connect to WebSocket server")]
+    Connect(#[source] tungstenite029::Error),
+    /// Receiving a value failed.
+    #[error(transparent)] Read(#[from] ReadError),
+    /// Sending a value failed.
+    #[error(transparent)] Write(#[from] WriteError),
+}
+
+/// Something that happened on a [`ReconnectingWebSocket`].
+#[derive(Debug)]
+pub enum ReconnectEvent<R> {
+    /// A connection was established and the messages returned from the `on_connect` hook were sent.
+    Connected,
+    /// The connection was lost or could not be established. The next attempt to connect is made after `retry_in`.
+    ///
+    /// `error` is `None` if the server closed the connection cleanly, see [`TypedWsStream`](crate::TypedWsStream).
+    Disconnected {
+        /// The reason the connection was lost or could not be established.
+        error: Option<ReconnectError>,
+        /// How long until the next attempt to connect.
+        retry_in: Duration,
+    },
+    /// A value was received.
+    Message(R),
+    /// A value could not be received or sent, but the connection is still usable. This happens e.g. if a received value could not be decoded.
+    Error(ReconnectError),
+}
+
+/// A typed WebSocket client which reconnects with exponential backoff when the connection is lost.
+///
+/// Values sent using [`send`](Self::send) while the client is disconnected are buffered and sent once the connection is reestablished. Values which were being sent when the connection was lost may be lost as well.
+///
+/// Received values and changes to the connection state are reported as [`ReconnectEvent`]s by the [`Stream`] implementation. The connection is managed by a task spawned on the current tokio runtime, which ends when this handle and all [`sender`](Self::sender)s are dropped.
+#[cfg_attr(docsrs, doc(cfg(feature = "reconnect")))]
+pub struct ReconnectingWebSocket<R, W> {
+    sender: mpsc::UnboundedSender<W>,
+    events: mpsc::UnboundedReceiver<ReconnectEvent<R>>,
+}
+
+impl<R: Protocol + Send + 'static, W: Protocol + Send + 'static> ReconnectingWebSocket<R, W> {
+    /// Starts connecting to the given server.
+    ///
+    /// After each (re)connection, the values returned from `on_connect` are sent before any buffered values, e.g. to authenticate or to renew subscriptions.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a tokio runtime.
+    pub fn new(request: impl IntoClientRequest + Clone + Send + Unpin + 'static, config: ReconnectConfig, on_connect: impl FnMut() -> Vec<W> + Send + 'static) -> Self {
+        let (sender, commands) = mpsc::unbounded();
+        let (event_sender, events) = mpsc::unbounded();
+        tokio::spawn(run(request, config, on_connect, commands, event_sender));
+        Self { sender, events }
+    }
+
+    /// Queues a value to be sent to the server, buffering it until a connection is available.
+    pub fn send(&self, value: W) {
+        // the task only ends once all senders are dropped
+        let _ = self.sender.unbounded_send(value);
+    }
+
+    /// Returns a handle which can be used to send values to the server from elsewhere, e.g. while this handle is being polled for events.
+    pub fn sender(&self) -> mpsc::UnboundedSender<W> {
+        self.sender.clone()
+    }
+}
+
+impl<R, W> Stream for ReconnectingWebSocket<R, W> {
+    type Item = ReconnectEvent<R>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ReconnectEvent<R>>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+enum Input<R, W> {
+    Received(Result<R, ReadError>),
+    Closed,
+    Send(W),
+    Shutdown,
+}
+
+/// Returns whether a read error indicates that the connection is no longer usable.
+fn is_read_disconnect(kind: &ReadErrorKind) -> bool {
+    matches!(kind, ReadErrorKind::EndOfStream | ReadErrorKind::Io(_) | ReadErrorKind::Tungstenite029(_) | ReadErrorKind::WebSocketClosed(_))
+}
+
+/// Returns whether a write error indicates that the connection is no longer usable.
+fn is_write_disconnect(kind: &WriteErrorKind) -> bool {
+    matches!(kind, WriteErrorKind::Io(_) | WriteErrorKind::Tungstenite029(_))
+}
+
+async fn run<R: Protocol + Send + 'static, W: Protocol + Send + 'static>(request: impl IntoClientRequest + Clone + Send + Unpin, config: ReconnectConfig, mut on_connect: impl FnMut() -> Vec<W> + Send, mut commands: mpsc::UnboundedReceiver<W>, events: mpsc::UnboundedSender<ReconnectEvent<R>>) {
+    let mut backoff = config.initial_backoff;
+    loop {
+        let error = match tokio_tungstenite029::connect_async(request.clone()).await {
+            Ok((sock, _)) => 'connection: {
+                backoff = config.initial_backoff;
+                let (sink, stream) = sock.split();
+                let (mut sink, stream) = typed_websocket::<Tungstenite029, R, W>(sink, stream, config.ws);
+                for value in on_connect() {
+                    if let Err(e) = sink.feed(value).await {
+                        if is_write_disconnect(&e.kind) { break 'connection Some(e.into()) }
+                        let _ = events.unbounded_send(ReconnectEvent::Error(e.into()));
+                    }
+                }
+                if let Err(e) = sink.flush().await {
+                    break 'connection Some(e.into())
+                }
+                if events.unbounded_send(ReconnectEvent::Connected).is_err() { return }
+                let mut inputs = stream::select(
+                    stream.map(Input::Received).chain(stream::once(future::ready(Input::Closed))),
+                    (&mut commands).map(Input::Send).chain(stream::once(future::ready(Input::Shutdown))),
+                );
+                loop {
+                    match inputs.next().await {
+                        Some(Input::Received(Ok(value))) => if events.unbounded_send(ReconnectEvent::Message(value)).is_err() { return },
+                        Some(Input::Received(Err(e))) => if is_read_disconnect(&e.kind) {
+                            break 'connection Some(e.into())
+                        } else if events.unbounded_send(ReconnectEvent::Error(e.into())).is_err() {
+                            return
+                        },
+                        Some(Input::Closed) | None => break 'connection None,
+                        Some(Input::Send(value)) => if let Err(e) = sink.send(value).await {
+                            if is_write_disconnect(&e.kind) { break 'connection Some(e.into()) }
+                            if events.unbounded_send(ReconnectEvent::Error(e.into())).is_err() { return }
+                        },
+                        Some(Input::Shutdown) => {
+                            let _ = sink.close().await;
+                            return
+                        }
+                    }
+                }
+            }
+            Err(e) => Some(ReconnectError::Connect(e)),
+        };
+        if events.unbounded_send(ReconnectEvent::Disconnected { error, retry_in: backoff }).is_err() { return }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(config.max_backoff);
+    }
+}
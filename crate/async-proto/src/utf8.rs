@@ -0,0 +1,99 @@
+//! A wrapper type for representing OS strings and paths portably.
+
+use {
+    std::{
+        ffi::OsString,
+        future::Future,
+        io::prelude::*,
+        path::PathBuf,
+        pin::Pin,
+    },
+    tokio::io::{
+        AsyncRead,
+        AsyncWrite,
+    },
+    crate::{
+        ErrorContext,
+        LengthPrefix,
+        LengthPrefixed,
+        Protocol,
+        ReadError,
+        WriteError,
+        WriteErrorKind,
+        impls::{
+            read_bytes,
+            read_bytes_sync,
+            write_bytes,
+            write_bytes_sync,
+        },
+    },
+};
+
+/// An [`OsString`] or [`PathBuf`] which is represented like a [`String`] on all platforms.
+///
+/// By default, OS strings and paths are represented as their raw bytes on Unix, so values which are not valid UTF-8 can't be read on other platforms. With this wrapper, writing such a value is an error on all platforms instead, and reading one fails with [`ReadErrorKind::Utf8`](crate::ReadErrorKind::Utf8). Values which are valid UTF-8 are represented the same way with or without this wrapper.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Utf8<T>(pub T);
+
+macro_rules! impl_protocol_utf8 {
+    ($ty:ident) => {
+        impl Protocol for Utf8<$ty> {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Self::read_length_prefixed(stream, LengthPrefix::default())
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                self.write_length_prefixed(sink, LengthPrefix::default())
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                Self::read_length_prefixed_sync(stream, LengthPrefix::default())
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                self.write_length_prefixed_sync(sink, LengthPrefix::default())
+            }
+        }
+
+        impl LengthPrefixed for Utf8<$ty> {
+            fn read_length_prefixed<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    let buf = read_bytes(stream, prefix, concat!("Utf8<", stringify!($ty), ">")).await?;
+                    Ok(Self($ty::from(String::from_utf8(buf).map_err(|e| ReadError {
+                        context: ErrorContext::BuiltIn { for_type: concat!("Utf8<", stringify!($ty), ">") },
+                        kind: e.into(),
+                    })?)))
+                })
+            }
+
+            fn write_length_prefixed<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W, prefix: LengthPrefix) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                Box::pin(async move {
+                    let s = self.0.to_str().ok_or(WriteError {
+                        context: ErrorContext::BuiltIn { for_type: concat!("Utf8<", stringify!($ty), ">") },
+                        kind: WriteErrorKind::NonUtf8,
+                    })?;
+                    write_bytes(sink, s.as_bytes(), prefix, concat!("Utf8<", stringify!($ty), ">")).await
+                })
+            }
+
+            fn read_length_prefixed_sync(stream: &mut impl Read, prefix: LengthPrefix) -> Result<Self, ReadError> {
+                let buf = read_bytes_sync(stream, prefix, concat!("Utf8<", stringify!($ty), ">"))?;
+                Ok(Self($ty::from(String::from_utf8(buf).map_err(|e| ReadError {
+                    context: ErrorContext::BuiltIn { for_type: concat!("Utf8<", stringify!($ty), ">") },
+                    kind: e.into(),
+                })?)))
+            }
+
+            fn write_length_prefixed_sync(&self, sink: &mut impl Write, prefix: LengthPrefix) -> Result<(), WriteError> {
+                let s = self.0.to_str().ok_or(WriteError {
+                    context: ErrorContext::BuiltIn { for_type: concat!("Utf8<", stringify!($ty), ">") },
+                    kind: WriteErrorKind::NonUtf8,
+                })?;
+                write_bytes_sync(sink, s.as_bytes(), prefix, concat!("Utf8<", stringify!($ty), ">"))
+            }
+        }
+    };
+}
+
+impl_protocol_utf8!(OsString);
+impl_protocol_utf8!(PathBuf);
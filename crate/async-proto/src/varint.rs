@@ -0,0 +1,194 @@
+//! Variable-length integer encodings.
+
+use {
+    std::{
+        future::Future,
+        io::prelude::*,
+        pin::Pin,
+    },
+    tokio::io::{
+        AsyncRead,
+        AsyncWrite,
+        AsyncWriteExt as _,
+    },
+    crate::{
+        ErrorContext,
+        Protocol,
+        ReadError,
+        ReadErrorKind,
+        WriteError,
+    },
+};
+
+/// The maximum number of bytes in the varint encoding of a [`u128`].
+const MAX_VARINT_LEN: usize = 19;
+
+/// An unsigned integer encoded as an [unsigned LEB128](https://en.wikipedia.org/wiki/LEB128#Unsigned_LEB128) varint.
+///
+/// Each byte carries 7 bits of the value, starting with the least significant group. The most significant bit of each byte is set if more bytes follow. This means that values below 128 take up a single byte, values below 16384 two bytes, and so on.
+///
+/// When reading, encodings with redundant trailing zero groups and values which do not fit into `T` are rejected, so each value has exactly one valid representation.
+///
+/// [`Protocol`] is implemented for `Varint<T>` for all primitive unsigned integer types `T`. For signed integers, see [`ZigZag`]. When deriving [`Protocol`], the `#[async_proto(varint)]` attribute can be used instead of wrapping fields in this type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Varint<T>(pub T);
+
+/// A signed integer encoded as a [ZigZag](https://protobuf.dev/programming-guides/encoding/#signed-ints)-mapped [`Varint`].
+///
+/// The value is first mapped to an unsigned integer such that numbers with a small absolute value, including negative ones, map to small numbers (`0` → `0`, `-1` → `1`, `1` → `2`, `-2` → `3`, and so on), which is then encoded as a [`Varint`].
+///
+/// [`Protocol`] is implemented for `ZigZag<T>` for all primitive signed integer types `T`. When deriving [`Protocol`], the `#[async_proto(varint)]` attribute can be used instead of wrapping fields in this type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZigZag<T>(pub T);
+
+/// Incrementally decodes a varint of the given bit width from individual bytes.
+struct Decoder {
+    bits: u32,
+    shift: u32,
+    value: u128,
+}
+
+impl Decoder {
+    fn new(bits: u32) -> Self {
+        Self { bits, shift: 0, value: 0 }
+    }
+
+    /// Returns the decoded value if `byte` was the last byte of the varint.
+    fn push(&mut self, byte: u8) -> Result<Option<u128>, ReadErrorKind> {
+        let group = u128::from(byte & 0x7f);
+        if self.shift > 0 && byte == 0 {
+            return Err(ReadErrorKind::VarintOverlong)
+        }
+        if self.shift >= self.bits || group >> (self.bits - self.shift).min(7) != 0 {
+            return Err(ReadErrorKind::VarintOverflow { bits: self.bits })
+        }
+        self.value |= group << self.shift;
+        self.shift += 7;
+        Ok((byte & 0x80 == 0).then_some(self.value))
+    }
+}
+
+/// Encodes `value` as a varint into `buf`, returning the number of bytes used.
+fn encode(mut value: u128, buf: &mut [u8; MAX_VARINT_LEN]) -> usize {
+    let mut len = 0;
+    loop {
+        let group = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = group;
+            return len + 1
+        }
+        buf[len] = group | 0x80;
+        len += 1;
+    }
+}
+
+pub(crate) async fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R, bits: u32, error_ctx: impl Fn() -> ErrorContext) -> Result<u128, ReadError> {
+    let mut decoder = Decoder::new(bits);
+    loop {
+        if let Some(value) = decoder.push(u8::read(stream).await?).map_err(|kind| ReadError {
+            context: error_ctx(),
+            kind,
+        })? {
+            return Ok(value)
+        }
+    }
+}
+
+pub(crate) async fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(sink: &'a mut W, value: u128, error_ctx: impl Fn() -> ErrorContext) -> Result<(), WriteError> {
+    let mut buf = [0; MAX_VARINT_LEN];
+    let len = encode(value, &mut buf);
+    sink.write_all(&buf[..len]).await.map_err(|e| WriteError {
+        context: error_ctx(),
+        kind: e.into(),
+    })
+}
+
+pub(crate) fn read_sync(stream: &mut impl Read, bits: u32, error_ctx: impl Fn() -> ErrorContext) -> Result<u128, ReadError> {
+    let mut decoder = Decoder::new(bits);
+    loop {
+        if let Some(value) = decoder.push(u8::read_sync(stream)?).map_err(|kind| ReadError {
+            context: error_ctx(),
+            kind,
+        })? {
+            return Ok(value)
+        }
+    }
+}
+
+pub(crate) fn write_sync(sink: &mut impl Write, value: u128, error_ctx: impl Fn() -> ErrorContext) -> Result<(), WriteError> {
+    let mut buf = [0; MAX_VARINT_LEN];
+    let len = encode(value, &mut buf);
+    sink.write_all(&buf[..len]).map_err(|e| WriteError {
+        context: error_ctx(),
+        kind: e.into(),
+    })
+}
+
+macro_rules! impl_protocol_varint {
+    ($ty:ty) => {
+        impl Protocol for Varint<$ty> {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    Ok(Self(read(stream, <$ty>::BITS, || ErrorContext::BuiltIn { for_type: concat!("Varint<", stringify!($ty), ">") }).await? as $ty))
+                })
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                Box::pin(async move {
+                    write(sink, self.0.into(), || ErrorContext::BuiltIn { for_type: concat!("Varint<", stringify!($ty), ">") }).await
+                })
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                Ok(Self(read_sync(stream, <$ty>::BITS, || ErrorContext::BuiltIn { for_type: concat!("Varint<", stringify!($ty), ">") })? as $ty))
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                write_sync(sink, self.0.into(), || ErrorContext::BuiltIn { for_type: concat!("Varint<", stringify!($ty), ">") })
+            }
+        }
+    };
+}
+
+impl_protocol_varint!(u8);
+impl_protocol_varint!(u16);
+impl_protocol_varint!(u32);
+impl_protocol_varint!(u64);
+impl_protocol_varint!(u128);
+
+macro_rules! impl_protocol_zigzag {
+    ($ty:ty, $unsigned:ty) => {
+        impl Protocol for ZigZag<$ty> {
+            fn read<'a, R: AsyncRead + Unpin + Send + 'a>(stream: &'a mut R) -> Pin<Box<dyn Future<Output = Result<Self, ReadError>> + Send + 'a>> {
+                Box::pin(async move {
+                    let zigzag = read(stream, <$ty>::BITS, || ErrorContext::BuiltIn { for_type: concat!("ZigZag<", stringify!($ty), ">") }).await? as $unsigned;
+                    Ok(Self((zigzag >> 1) as $ty ^ -((zigzag & 1) as $ty)))
+                })
+            }
+
+            fn write<'a, W: AsyncWrite + Unpin + Send + 'a>(&'a self, sink: &'a mut W) -> Pin<Box<dyn Future<Output = Result<(), WriteError>> + Send + 'a>> {
+                Box::pin(async move {
+                    let zigzag = ((self.0 << 1) ^ (self.0 >> (<$ty>::BITS - 1))) as $unsigned;
+                    write(sink, zigzag.into(), || ErrorContext::BuiltIn { for_type: concat!("ZigZag<", stringify!($ty), ">") }).await
+                })
+            }
+
+            fn read_sync(stream: &mut impl Read) -> Result<Self, ReadError> {
+                let zigzag = read_sync(stream, <$ty>::BITS, || ErrorContext::BuiltIn { for_type: concat!("ZigZag<", stringify!($ty), ">") })? as $unsigned;
+                Ok(Self((zigzag >> 1) as $ty ^ -((zigzag & 1) as $ty)))
+            }
+
+            fn write_sync(&self, sink: &mut impl Write) -> Result<(), WriteError> {
+                let zigzag = ((self.0 << 1) ^ (self.0 >> (<$ty>::BITS - 1))) as $unsigned;
+                write_sync(sink, zigzag.into(), || ErrorContext::BuiltIn { for_type: concat!("ZigZag<", stringify!($ty), ">") })
+            }
+        }
+    };
+}
+
+impl_protocol_zigzag!(i8, u8);
+impl_protocol_zigzag!(i16, u16);
+impl_protocol_zigzag!(i32, u32);
+impl_protocol_zigzag!(i64, u64);
+impl_protocol_zigzag!(i128, u128);
@@ -0,0 +1,1240 @@
+//! Configuration and the generic implementation of the WebSocket support of this crate.
+
+use {
+    std::{
+        io,
+        num::NonZeroUsize,
+        pin::Pin,
+        sync::{
+            Arc,
+            OnceLock,
+        },
+        task::{
+            Context,
+            Poll,
+            ready,
+        },
+        time::Duration,
+    },
+    futures::{
+        Sink,
+        SinkExt as _,
+        future::{
+            self,
+            Either,
+        },
+        stream::{
+            self,
+            Stream,
+            StreamExt as _,
+            TryStreamExt as _,
+        },
+    },
+    tokio::io::{
+        AsyncRead,
+        ReadBuf,
+    },
+    crate::{
+        ErrorContext,
+        Protocol,
+        ReadError,
+        ReadErrorKind,
+        WriteError,
+        WriteErrorKind,
+    },
+};
+#[cfg(feature = "async-tungstenite032")] use async_tungstenite032::tungstenite as tungstenite028;
+#[cfg(feature = "axum")] use axum::extract::ws as axum_ws;
+#[cfg(feature = "keepalive")] use {
+    futures::{
+        FutureExt as _,
+        lock::{
+            Mutex,
+            OwnedMutexGuard,
+            OwnedMutexLockFuture,
+        },
+    },
+    tokio::time::{
+        Instant,
+        timeout_at,
+    },
+};
+#[cfg(feature = "websocket-json")] use serde::{
+    Serialize,
+    de::DeserializeOwned,
+};
+#[cfg(feature = "tokio-tungstenite021")] use tokio_tungstenite021::tungstenite as tungstenite021;
+#[cfg(feature = "tokio-tungstenite024")] use tokio_tungstenite024::tungstenite as tungstenite024;
+#[cfg(feature = "tokio-tungstenite029")] use tokio_tungstenite029::tungstenite as tungstenite029;
+
+/// The maximum message size that can be sent and received by tokio-tungstenite without errors on the default configuration.
+const WS_MAX_MESSAGE_SIZE: NonZeroUsize = NonZeroUsize::new(16777216).unwrap();
+
+/// The close code indicating a normal closure, see [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455#section-7.4.1).
+const CLOSE_CODE_NORMAL: u16 = 1000;
+
+/// The close code indicating that the peer is going away, e.g. because a browser tab was closed or a server is restarting, see [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455#section-7.4.1).
+const CLOSE_CODE_GOING_AWAY: u16 = 1001;
+
+/// A WebSocket library which can be used to send and receive values of [`Protocol`] types.
+///
+/// This crate implements this trait for marker types representing the supported WebSocket libraries, such as `Tungstenite029`. Implementing it for another library makes [`Protocol::read_ws_transport`], [`Protocol::write_ws_transport`], and [`typed_websocket`] available for any [`Stream`] or [`Sink`] of that library's messages.
+pub trait WebSocketTransport {
+    /// The type of messages sent and received using this library.
+    type Message: Send;
+    /// The error type returned when sending or receiving a message fails.
+    type Error: Send;
+
+    /// Constructs a binary message.
+    fn binary_message(data: Vec<u8>) -> Self::Message;
+    /// Constructs a text message.
+    fn text_message(text: String) -> Self::Message;
+    /// Constructs a `Ping` message, sent if keepalive pings are configured (see the `keepalive` feature flag).
+    fn ping_message() -> Self::Message;
+    /// Determines what kind of message this is.
+    fn message_kind(msg: &Self::Message) -> WsMessageKind<'_>;
+    /// Returns the error for receiving a message which is not expected at this point, e.g. a text message other than the header of a chunked value.
+    fn unexpected_message(msg: Self::Message) -> ReadErrorKind;
+    /// Converts an error which occurred while receiving a message.
+    fn read_error(e: Self::Error) -> ReadErrorKind;
+    /// Converts an error which occurred while sending a message.
+    fn write_error(e: Self::Error) -> WriteErrorKind;
+}
+
+/// What kind of message a [`WebSocketTransport::Message`] is.
+#[derive(Debug, Clone, Copy)]
+pub enum WsMessageKind<'a> {
+    /// A binary message with the given payload.
+    Binary(&'a [u8]),
+    /// A text message with the given payload.
+    Text(&'a str),
+    /// A `Ping` message. The WebSocket library is expected to respond to these on its own.
+    Ping,
+    /// A `Pong` message.
+    Pong,
+    /// A `Close` message, with the close code and reason if any.
+    Close(Option<(u16, &'a str)>),
+    /// Any other kind of message, e.g. a raw frame.
+    Other,
+}
+
+/// Represents [`tokio-tungstenite` 0.21](https://docs.rs/tokio-tungstenite/0.21) as a [`WebSocketTransport`].
+#[cfg(feature = "tokio-tungstenite021")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
+pub enum Tungstenite021 {}
+
+#[cfg(feature = "tokio-tungstenite021")]
+impl WebSocketTransport for Tungstenite021 {
+    type Message = tungstenite021::Message;
+    type Error = tungstenite021::Error;
+
+    fn binary_message(data: Vec<u8>) -> Self::Message { tungstenite021::Message::binary(data) }
+    fn text_message(text: String) -> Self::Message { tungstenite021::Message::text(text) }
+    fn ping_message() -> Self::Message { tungstenite021::Message::Ping(Vec::default()) }
+
+    fn message_kind(msg: &Self::Message) -> WsMessageKind<'_> {
+        match msg {
+            tungstenite021::Message::Binary(data) => WsMessageKind::Binary(data),
+            tungstenite021::Message::Text(text) => WsMessageKind::Text(text),
+            tungstenite021::Message::Ping(_) => WsMessageKind::Ping,
+            tungstenite021::Message::Pong(_) => WsMessageKind::Pong,
+            tungstenite021::Message::Close(frame) => WsMessageKind::Close(frame.as_ref().map(|frame| (frame.code.into(), &*frame.reason))),
+            tungstenite021::Message::Frame(_) => WsMessageKind::Other,
+        }
+    }
+
+    fn unexpected_message(msg: Self::Message) -> ReadErrorKind {
+        match msg {
+            tungstenite021::Message::Text(text) => ReadErrorKind::WebSocketTextMessage024(text),
+            _ => ReadErrorKind::MessageKind021(msg),
+        }
+    }
+
+    fn read_error(e: Self::Error) -> ReadErrorKind { e.into() }
+    fn write_error(e: Self::Error) -> WriteErrorKind { e.into() }
+}
+
+/// Represents [`tokio-tungstenite` 0.24](https://docs.rs/tokio-tungstenite/0.24) as a [`WebSocketTransport`].
+#[cfg(feature = "tokio-tungstenite024")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
+pub enum Tungstenite024 {}
+
+#[cfg(feature = "tokio-tungstenite024")]
+impl WebSocketTransport for Tungstenite024 {
+    type Message = tungstenite024::Message;
+    type Error = tungstenite024::Error;
+
+    fn binary_message(data: Vec<u8>) -> Self::Message { tungstenite024::Message::binary(data) }
+    fn text_message(text: String) -> Self::Message { tungstenite024::Message::text(text) }
+    fn ping_message() -> Self::Message { tungstenite024::Message::Ping(Vec::default()) }
+
+    fn message_kind(msg: &Self::Message) -> WsMessageKind<'_> {
+        match msg {
+            tungstenite024::Message::Binary(data) => WsMessageKind::Binary(data),
+            tungstenite024::Message::Text(text) => WsMessageKind::Text(text),
+            tungstenite024::Message::Ping(_) => WsMessageKind::Ping,
+            tungstenite024::Message::Pong(_) => WsMessageKind::Pong,
+            tungstenite024::Message::Close(frame) => WsMessageKind::Close(frame.as_ref().map(|frame| (frame.code.into(), &*frame.reason))),
+            tungstenite024::Message::Frame(_) => WsMessageKind::Other,
+        }
+    }
+
+    fn unexpected_message(msg: Self::Message) -> ReadErrorKind {
+        match msg {
+            tungstenite024::Message::Text(text) => ReadErrorKind::WebSocketTextMessage024(text),
+            _ => ReadErrorKind::MessageKind024(msg),
+        }
+    }
+
+    fn read_error(e: Self::Error) -> ReadErrorKind { e.into() }
+    fn write_error(e: Self::Error) -> WriteErrorKind { e.into() }
+}
+
+/// Represents [`tokio-tungstenite` 0.29](https://docs.rs/tokio-tungstenite/0.29) as a [`WebSocketTransport`].
+#[cfg(feature = "tokio-tungstenite029")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
+pub enum Tungstenite029 {}
+
+#[cfg(feature = "tokio-tungstenite029")]
+impl WebSocketTransport for Tungstenite029 {
+    type Message = tungstenite029::Message;
+    type Error = tungstenite029::Error;
+
+    fn binary_message(data: Vec<u8>) -> Self::Message { tungstenite029::Message::binary(data) }
+    fn text_message(text: String) -> Self::Message { tungstenite029::Message::text(text) }
+    fn ping_message() -> Self::Message { tungstenite029::Message::Ping(tungstenite029::Bytes::default()) }
+
+    fn message_kind(msg: &Self::Message) -> WsMessageKind<'_> {
+        match msg {
+            tungstenite029::Message::Binary(data) => WsMessageKind::Binary(data),
+            tungstenite029::Message::Text(text) => WsMessageKind::Text(text),
+            tungstenite029::Message::Ping(_) => WsMessageKind::Ping,
+            tungstenite029::Message::Pong(_) => WsMessageKind::Pong,
+            tungstenite029::Message::Close(frame) => WsMessageKind::Close(frame.as_ref().map(|frame| (frame.code.into(), &*frame.reason))),
+            tungstenite029::Message::Frame(_) => WsMessageKind::Other,
+        }
+    }
+
+    fn unexpected_message(msg: Self::Message) -> ReadErrorKind {
+        match msg {
+            tungstenite029::Message::Text(text) => ReadErrorKind::WebSocketTextMessage029(text),
+            _ => ReadErrorKind::MessageKind029(msg),
+        }
+    }
+
+    fn read_error(e: Self::Error) -> ReadErrorKind { e.into() }
+    fn write_error(e: Self::Error) -> WriteErrorKind { e.into() }
+}
+
+/// Represents [`async-tungstenite` 0.32](https://docs.rs/async-tungstenite/0.32) as a [`WebSocketTransport`].
+///
+/// Since `async-tungstenite` works with multiple async runtimes, this crate does not offer functions for establishing connections using it. Instead, the halves of a split `async_tungstenite::WebSocketStream` can be passed to [`typed_websocket`].
+#[cfg(feature = "async-tungstenite032")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-tungstenite032")))]
+pub enum AsyncTungstenite032 {}
+
+#[cfg(feature = "async-tungstenite032")]
+impl WebSocketTransport for AsyncTungstenite032 {
+    type Message = tungstenite028::Message;
+    type Error = tungstenite028::Error;
+
+    fn binary_message(data: Vec<u8>) -> Self::Message { tungstenite028::Message::binary(data) }
+    fn text_message(text: String) -> Self::Message { tungstenite028::Message::text(text) }
+    fn ping_message() -> Self::Message { tungstenite028::Message::Ping(tungstenite028::Bytes::default()) }
+
+    fn message_kind(msg: &Self::Message) -> WsMessageKind<'_> {
+        match msg {
+            tungstenite028::Message::Binary(data) => WsMessageKind::Binary(data),
+            tungstenite028::Message::Text(text) => WsMessageKind::Text(text),
+            tungstenite028::Message::Ping(_) => WsMessageKind::Ping,
+            tungstenite028::Message::Pong(_) => WsMessageKind::Pong,
+            tungstenite028::Message::Close(frame) => WsMessageKind::Close(frame.as_ref().map(|frame| (frame.code.into(), &*frame.reason))),
+            tungstenite028::Message::Frame(_) => WsMessageKind::Other,
+        }
+    }
+
+    fn unexpected_message(msg: Self::Message) -> ReadErrorKind { ReadErrorKind::UnexpectedWebSocketMessage(format!("{msg:?}")) }
+    fn read_error(e: Self::Error) -> ReadErrorKind { ReadErrorKind::WebSocketTransport(Box::new(e)) }
+    fn write_error(e: Self::Error) -> WriteErrorKind { WriteErrorKind::WebSocketTransport(Box::new(e)) }
+}
+
+/// Represents the WebSocket support of [`axum` 0.8](https://docs.rs/axum/0.8) as a [`WebSocketTransport`].
+///
+/// See also [`TypedWebSocket`] and the `read_ws_axum`/`write_ws_axum` methods of [`Protocol`].
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+pub enum Axum {}
+
+#[cfg(feature = "axum")]
+impl WebSocketTransport for Axum {
+    type Message = axum_ws::Message;
+    type Error = axum::Error;
+
+    fn binary_message(data: Vec<u8>) -> Self::Message { axum_ws::Message::binary(data) }
+    fn text_message(text: String) -> Self::Message { axum_ws::Message::text(text) }
+    fn ping_message() -> Self::Message { axum_ws::Message::Ping(Default::default()) }
+
+    fn message_kind(msg: &Self::Message) -> WsMessageKind<'_> {
+        match msg {
+            axum_ws::Message::Binary(data) => WsMessageKind::Binary(data),
+            axum_ws::Message::Text(text) => WsMessageKind::Text(text.as_str()),
+            axum_ws::Message::Ping(_) => WsMessageKind::Ping,
+            axum_ws::Message::Pong(_) => WsMessageKind::Pong,
+            axum_ws::Message::Close(frame) => WsMessageKind::Close(frame.as_ref().map(|frame| (frame.code, frame.reason.as_str()))),
+        }
+    }
+
+    fn unexpected_message(msg: Self::Message) -> ReadErrorKind { ReadErrorKind::UnexpectedWebSocketMessage(format!("{msg:?}")) }
+    fn read_error(e: Self::Error) -> ReadErrorKind { ReadErrorKind::WebSocketTransport(Box::new(e)) }
+    fn write_error(e: Self::Error) -> WriteErrorKind { WriteErrorKind::WebSocketTransport(Box::new(e)) }
+}
+
+/// Configures how messages are sent and received using the WebSocket methods of [`Protocol`] and the typed WebSocket helpers such as [`typed_websocket`].
+///
+/// Values whose encoding is longer than [`max_chunk_size`](Self::max_chunk_size) are sent as a text message announcing the total length, followed by binary messages containing the encoded value in chunks. On the receiving side, the value is decoded while the chunks are being received, so the chunks are never buffered all at once.
+///
+/// A `WsConfig` matching the limits of a `tungstenite` connection can be created from its `WebSocketConfig` using [`From`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WsConfig {
+    /// The maximum size of a single binary message, in bytes. Larger values are split into chunks of this size.
+    ///
+    /// This should not exceed the maximum message or frame size the peer accepts. Defaults to 16 MiB, which can be sent and received by `tokio-tungstenite` without errors on its default configuration.
+    pub max_chunk_size: NonZeroUsize,
+    /// The maximum size of an encoded value, in bytes, including all of its chunks. Larger values are rejected, both when reading and when writing.
+    ///
+    /// When reading a chunked message, the length announced by the peer is checked against this limit before any chunks are read. Defaults to no limit.
+    pub max_message_size: usize,
+    /// If set, the typed WebSocket helpers periodically ping the peer and end the stream with an error if the peer stops responding.
+    ///
+    /// This has no effect on the WebSocket methods of [`Protocol`], since those can't send messages while reading. Defaults to no keepalive.
+    ///
+    /// Keepalive pings require the `keepalive` feature flag and are not sent without it. They use tokio's timer, so they require a tokio runtime with the time driver enabled.
+    pub keepalive: Option<WsKeepalive>,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self {
+            max_chunk_size: WS_MAX_MESSAGE_SIZE,
+            max_message_size: usize::MAX,
+            keepalive: None,
+        }
+    }
+}
+
+macro_rules! impl_ws_config_from {
+    ($feature:literal, $tungstenite:ident) => {
+        #[cfg(feature = $feature)]
+        #[cfg_attr(docsrs, doc(cfg(feature = $feature)))]
+        impl From<$tungstenite::protocol::WebSocketConfig> for WsConfig {
+            /// Chooses the chunk size such that each chunk fits into a single message and frame as limited by the given config. The size of chunked messages is not limited.
+            fn from(config: $tungstenite::protocol::WebSocketConfig) -> Self {
+                let max_chunk_size = config.max_message_size.unwrap_or(usize::MAX).min(config.max_frame_size.unwrap_or(usize::MAX));
+                Self {
+                    max_chunk_size: NonZeroUsize::new(max_chunk_size).unwrap_or(NonZeroUsize::MIN),
+                    ..Self::default()
+                }
+            }
+        }
+    };
+}
+
+impl_ws_config_from!("tokio-tungstenite021", tungstenite021);
+impl_ws_config_from!("tokio-tungstenite024", tungstenite024);
+impl_ws_config_from!("tokio-tungstenite029", tungstenite029);
+impl_ws_config_from!("async-tungstenite032", tungstenite028);
+
+/// Configures the keepalive pings sent by the typed WebSocket helpers, see [`WsConfig::keepalive`].
+///
+/// Pings are only sent and timeouts only detected while the stream returned from the helper is being polled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WsKeepalive {
+    /// A `Ping` message is sent if no message has been received from the peer for this long, and repeated at this interval until a message is received.
+    pub interval: Duration,
+    /// If no message (including `Pong` messages) is received from the peer for this long after sending a ping, the stream yields an I/O error with kind [`TimedOut`](io::ErrorKind::TimedOut) and ends.
+    pub timeout: Duration,
+}
+
+/// The close code and reason sent by a WebSocket peer when closing the connection.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WsCloseFrame {
+    /// The close code, see [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455#section-7.4).
+    pub code: u16,
+    /// The reason given for closing the connection. May be empty.
+    pub reason: String,
+}
+
+#[cfg(feature = "tokio-tungstenite021")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite021")))]
+impl From<tungstenite021::protocol::CloseFrame<'_>> for WsCloseFrame {
+    fn from(frame: tungstenite021::protocol::CloseFrame<'_>) -> Self {
+        Self {
+            code: frame.code.into(),
+            reason: frame.reason.into_owned(),
+        }
+    }
+}
+
+#[cfg(feature = "tokio-tungstenite024")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite024")))]
+impl From<tungstenite024::protocol::CloseFrame<'_>> for WsCloseFrame {
+    fn from(frame: tungstenite024::protocol::CloseFrame<'_>) -> Self {
+        Self {
+            code: frame.code.into(),
+            reason: frame.reason.into_owned(),
+        }
+    }
+}
+
+#[cfg(feature = "tokio-tungstenite029")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
+impl From<tungstenite029::protocol::CloseFrame> for WsCloseFrame {
+    fn from(frame: tungstenite029::protocol::CloseFrame) -> Self {
+        Self {
+            code: frame.code.into(),
+            reason: frame.reason.as_str().to_owned(),
+        }
+    }
+}
+
+/// What [`receive`] received from a WebSocket.
+pub(crate) enum Received<P> {
+    /// A value was received.
+    Value(P),
+    /// The peer closed the connection between values.
+    Closed(Option<WsCloseFrame>),
+    /// The underlying stream ended between values.
+    EndOfStream,
+}
+
+/// Keeps track of the binary chunks of a value which is being decoded while it is received.
+struct Chunks<T: WebSocketTransport> {
+    /// The length announced in the text message preceding the chunks.
+    len: usize,
+    /// The total size of the chunks received so far.
+    received: usize,
+    /// The chunk currently being read, and how much of it has already been read.
+    chunk: Option<(T::Message, usize)>,
+    /// The error which caused the reader to fail, reported instead of the resulting I/O error.
+    error: Option<ReadError>,
+    /// Whether receiving a chunk failed, in which case the remaining chunks can't be skipped.
+    failed: bool,
+}
+
+impl<T: WebSocketTransport> Chunks<T> {
+    fn new(len: usize) -> Self {
+        Self { len, received: 0, chunk: None, error: None, failed: false }
+    }
+
+    /// Copies as much of the current chunk as possible into `buf`. Returns `None` if another chunk needs to be received first.
+    fn read_buffered(&mut self, buf: &mut [u8]) -> Option<usize> {
+        if let Some((msg, offset)) = &mut self.chunk {
+            if let WsMessageKind::Binary(data) = T::message_kind(msg) && *offset < data.len() {
+                let n = buf.len().min(data.len() - *offset);
+                buf[..n].copy_from_slice(&data[*offset..*offset + n]);
+                *offset += n;
+                return Some(n)
+            }
+            self.chunk = None;
+        }
+        (self.received >= self.len).then_some(0)
+    }
+
+    /// Handles a message received while waiting for the next chunk.
+    fn push(&mut self, res: Result<Option<T::Message>, ReadErrorKind>) -> io::Result<()> {
+        let kind = match res {
+            Ok(Some(msg)) => match T::message_kind(&msg) {
+                WsMessageKind::Binary(data) => if self.received + data.len() > self.len {
+                    ReadErrorKind::MaxLen { len: (self.received + data.len()) as u64, max_len: self.len as u64 }
+                } else {
+                    self.received += data.len();
+                    self.chunk = Some((msg, 0));
+                    return Ok(())
+                },
+                WsMessageKind::Ping | WsMessageKind::Pong => return Ok(()), // pings are answered by the WebSocket library
+                WsMessageKind::Close(frame) => ReadErrorKind::WebSocketClosed(frame.map(|(code, reason)| WsCloseFrame { code, reason: reason.to_owned() })),
+                WsMessageKind::Text(_) | WsMessageKind::Other => T::unexpected_message(msg),
+            },
+            Ok(None) => ReadErrorKind::EndOfStream,
+            Err(kind) => kind,
+        };
+        self.failed = true;
+        self.error = Some(ReadError {
+            context: ErrorContext::DefaultImpl,
+            kind,
+        });
+        Err(io::Error::other("failed to receive WebSocket message chunk"))
+    }
+
+    /// Checks that the announced length doesn't exceed the maximum message size.
+    fn check_len(&self, config: WsConfig) -> Result<(), ReadError> {
+        if self.len > config.max_message_size {
+            Err(ReadError {
+                context: ErrorContext::DefaultImpl,
+                kind: ReadErrorKind::MaxLen { len: self.len as u64, max_len: config.max_message_size as u64 },
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that the decoded value took up exactly the announced length.
+    fn finish(&self) -> Result<(), ReadError> {
+        let unread = self.len - self.received + self.chunk.as_ref().map_or(0, |(msg, offset)| match T::message_kind(msg) {
+            WsMessageKind::Binary(data) => data.len() - offset,
+            _ => 0,
+        });
+        if unread > 0 {
+            Err(ReadError {
+                context: ErrorContext::DefaultImpl,
+                kind: ReadErrorKind::WebSocketTrailingData { len: self.len as u64, read: (self.len - unread) as u64 },
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the error to report if decoding a value from the chunks failed.
+    fn error(&mut self, ReadError { context, kind }: ReadError) -> ReadError {
+        self.error.take().unwrap_or_else(|| ReadError {
+            context: ErrorContext::WebSocket {
+                source: Box::new(context),
+            },
+            kind,
+        })
+    }
+}
+
+/// Exposes the binary chunks of a value received from a stream of WebSocket messages as an [`AsyncRead`], so the value can be decoded without buffering all of them.
+struct ChunkReader<'a, T: WebSocketTransport, St> {
+    stream: &'a mut St,
+    chunks: Chunks<T>,
+}
+
+// the fields are never pinned
+impl<T: WebSocketTransport, St> Unpin for ChunkReader<'_, T, St> {}
+
+impl<T: WebSocketTransport, St: Stream<Item = Result<T::Message, ReadErrorKind>> + Unpin> AsyncRead for ChunkReader<'_, T, St> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        loop {
+            if let Some(n) = self.chunks.read_buffered(buf.initialize_unfilled()) {
+                buf.advance(n);
+                return Poll::Ready(Ok(()))
+            }
+            let res = ready!(self.stream.poll_next_unpin(cx)).transpose();
+            self.chunks.push(res)?;
+        }
+    }
+}
+
+/// Exposes the binary chunks of a value received from a sync WebSocket as a [`Read`](io::Read), so the value can be decoded without buffering all of them.
+struct SyncChunkReader<'a, T: WebSocketTransport, F> {
+    read: &'a mut F,
+    chunks: Chunks<T>,
+}
+
+impl<T: WebSocketTransport, F: FnMut() -> Result<T::Message, T::Error>> io::Read for SyncChunkReader<'_, T, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(n) = self.chunks.read_buffered(buf) {
+                return Ok(n)
+            }
+            let res = (self.read)().map(Some).map_err(T::read_error);
+            self.chunks.push(res)?;
+        }
+    }
+}
+
+/// Parses the length announced in the text message preceding a chunked value, if this is such a message.
+fn chunked_len(text: &str) -> Option<Result<usize, ReadError>> {
+    let len = text.strip_prefix('m')?;
+    Some(len.parse::<usize>().map_err(|e| ReadError {
+        context: ErrorContext::DefaultImpl,
+        kind: e.into(),
+    }))
+}
+
+fn decode<P: Protocol>(mut buf: &[u8], config: WsConfig) -> Result<P, ReadError> {
+    if buf.len() > config.max_message_size {
+        return Err(ReadError {
+            context: ErrorContext::DefaultImpl,
+            kind: ReadErrorKind::MaxLen { len: buf.len() as u64, max_len: config.max_message_size as u64 },
+        })
+    }
+    P::read_sync(&mut buf).map_err(|ReadError { context, kind }| ReadError {
+        context: ErrorContext::WebSocket {
+            source: Box::new(context),
+        },
+        kind,
+    })
+}
+
+/// Receives the next value from a stream of WebSocket messages, skipping `Ping` and `Pong` messages.
+///
+/// Values sent in chunks are decoded while the chunks are being received, so only one chunk needs to be held in memory at a time in addition to the value itself.
+#[allow(clippy::type_complexity)]
+pub(crate) async fn receive<T: WebSocketTransport, P: Protocol, St: Stream<Item = Result<T::Message, ReadErrorKind>> + Unpin + Send>(stream: &mut St, config: WsConfig, decode_text: Option<fn(&str) -> Result<P, ReadErrorKind>>, context: fn() -> ErrorContext) -> Result<Received<P>, ReadError> {
+    loop {
+        let Some(msg) = stream.try_next().await.map_err(|kind| ReadError { context: context(), kind })? else { return Ok(Received::EndOfStream) };
+        match T::message_kind(&msg) {
+            WsMessageKind::Binary(data) => return decode(data, config).map(Received::Value),
+            WsMessageKind::Text(text) => if let Some(len) = chunked_len(text) {
+                let mut reader = ChunkReader::<T, _> { stream: &mut *stream, chunks: Chunks::new(len?) };
+                let e = match reader.chunks.check_len(config) {
+                    Ok(()) => match P::read(&mut reader).await {
+                        Ok(value) => match reader.chunks.finish() {
+                            Ok(()) => return Ok(Received::Value(value)),
+                            Err(e) => e,
+                        },
+                        Err(e) => reader.chunks.error(e),
+                    },
+                    Err(e) => e,
+                };
+                if !reader.chunks.failed {
+                    // skip the remaining chunks so the next value starts at a message boundary
+                    let _ = tokio::io::copy(&mut reader, &mut tokio::io::sink()).await;
+                }
+                return Err(e)
+            } else if let Some(decode_text) = decode_text {
+                if text.len() > config.max_message_size {
+                    return Err(ReadError {
+                        context: ErrorContext::DefaultImpl,
+                        kind: ReadErrorKind::MaxLen { len: text.len() as u64, max_len: config.max_message_size as u64 },
+                    })
+                }
+                return decode_text(text).map(Received::Value).map_err(|kind| ReadError {
+                    context: ErrorContext::DefaultImpl,
+                    kind,
+                })
+            } else {
+                return Err(ReadError {
+                    context: ErrorContext::DefaultImpl,
+                    kind: T::unexpected_message(msg),
+                })
+            },
+            WsMessageKind::Ping | WsMessageKind::Pong => {} // pings are answered by the WebSocket library
+            WsMessageKind::Close(frame) => return Ok(Received::Closed(frame.map(|(code, reason)| WsCloseFrame { code, reason: reason.to_owned() }))),
+            WsMessageKind::Other => return Err(ReadError {
+                context: ErrorContext::DefaultImpl,
+                kind: T::unexpected_message(msg),
+            }),
+        }
+    }
+}
+
+/// Encodes a value as a single binary message, or as a text message announcing the length followed by binary chunks if it's longer than [`WsConfig::max_chunk_size`].
+pub(crate) fn encode<T: WebSocketTransport>(value: &impl Protocol, config: WsConfig) -> Result<Vec<T::Message>, WriteError> {
+    let mut buf = Vec::default();
+    value.write_sync(&mut buf).map_err(|WriteError { context, kind }| WriteError {
+        context: ErrorContext::WebSocket {
+            source: Box::new(context),
+        },
+        kind,
+    })?;
+    if buf.len() > config.max_message_size {
+        return Err(WriteError {
+            context: ErrorContext::DefaultImpl,
+            kind: WriteErrorKind::MaxLen { len: buf.len() as u64, max_len: config.max_message_size as u64 },
+        })
+    }
+    Ok(if buf.len() <= config.max_chunk_size.get() {
+        vec![T::binary_message(buf)]
+    } else {
+        let mut msgs = vec![T::text_message(format!("m{}", buf.len()))];
+        msgs.extend(buf.chunks(config.max_chunk_size.get()).map(|chunk| T::binary_message(chunk.to_vec())));
+        msgs
+    })
+}
+
+/// Encodes a value as a single text message. Text messages are never chunked.
+fn encode_text_message<T: WebSocketTransport, W>(value: &W, encode_text: fn(&W) -> Result<String, WriteErrorKind>, config: WsConfig) -> Result<Vec<T::Message>, WriteError> {
+    let text = encode_text(value).map_err(|kind| WriteError {
+        context: ErrorContext::DefaultImpl,
+        kind,
+    })?;
+    if text.len() > config.max_message_size {
+        return Err(WriteError {
+            context: ErrorContext::DefaultImpl,
+            kind: WriteErrorKind::MaxLen { len: text.len() as u64, max_len: config.max_message_size as u64 },
+        })
+    }
+    Ok(vec![T::text_message(text)])
+}
+
+pub(crate) fn read_sync<T: WebSocketTransport, P: Protocol>(mut read: impl FnMut() -> Result<T::Message, T::Error>, config: WsConfig) -> Result<P, ReadError> {
+    loop {
+        let msg = read().map_err(|e| ReadError {
+            context: ErrorContext::DefaultImpl,
+            kind: T::read_error(e),
+        })?;
+        match T::message_kind(&msg) {
+            WsMessageKind::Binary(data) => return decode(data, config),
+            WsMessageKind::Text(text) => if let Some(len) = chunked_len(text) {
+                let mut reader = SyncChunkReader::<T, _> { read: &mut read, chunks: Chunks::new(len?) };
+                let res = reader.chunks.check_len(config).and_then(|()| match P::read_sync(&mut reader) {
+                    Ok(value) => reader.chunks.finish().map(|()| value),
+                    Err(e) => Err(reader.chunks.error(e)),
+                });
+                if res.is_err() && !reader.chunks.failed {
+                    // skip the remaining chunks so the next value starts at a message boundary
+                    let _ = io::copy(&mut reader, &mut io::sink());
+                }
+                return res
+            } else {
+                return Err(ReadError {
+                    context: ErrorContext::DefaultImpl,
+                    kind: T::unexpected_message(msg),
+                })
+            },
+            WsMessageKind::Ping | WsMessageKind::Pong => {} // pings are answered by the WebSocket library
+            WsMessageKind::Close(frame) => return Err(ReadError {
+                context: ErrorContext::DefaultImpl,
+                kind: ReadErrorKind::WebSocketClosed(frame.map(|(code, reason)| WsCloseFrame { code, reason: reason.to_owned() })),
+            }),
+            WsMessageKind::Other => return Err(ReadError {
+                context: ErrorContext::DefaultImpl,
+                kind: T::unexpected_message(msg),
+            }),
+        }
+    }
+}
+
+pub(crate) fn write_sync<T: WebSocketTransport>(value: &impl Protocol, mut send: impl FnMut(T::Message) -> Result<(), T::Error>, config: WsConfig) -> Result<(), WriteError> {
+    for msg in encode::<T>(value, config)? {
+        send(msg).map_err(|e| WriteError {
+            context: ErrorContext::DefaultImpl,
+            kind: T::write_error(e),
+        })?;
+    }
+    Ok(())
+}
+
+/// Turns the sink and stream halves of a WebSocket connection into a typed sink/stream pair.
+///
+/// This is the generic implementation behind functions like `websocket029` and can be used with any [`WebSocketTransport`], e.g. with the halves of a split `async_tungstenite::WebSocketStream`.
+///
+/// `Ping` and `Pong` messages are handled transparently. See [`TypedWsStream`] for how the end of the connection is reported.
+pub fn typed_websocket<T: WebSocketTransport, R: Protocol, W: Protocol>(sink: impl Sink<T::Message, Error = T::Error> + Unpin + Send, stream: impl Stream<Item = Result<T::Message, T::Error>> + Unpin + Send, config: WsConfig) -> (impl Sink<W, Error = WriteError> + Unpin, TypedWsStream<impl Stream<Item = Result<R, ReadError>> + Unpin>) {
+    typed_websocket_with_codec::<T, R, W>(sink, stream, config, None)
+}
+
+/// Functions for sending and receiving values as text messages, used for the [JSON format](WsFormat::Json).
+pub(crate) struct TextCodec<R, W> {
+    decode: fn(&str) -> Result<R, ReadErrorKind>,
+    /// If this is `None`, values are sent using the binary format but text messages are still accepted.
+    #[allow(clippy::type_complexity)]
+    encode: Option<fn(&W) -> Result<String, WriteErrorKind>>,
+}
+
+impl<R, W> Clone for TextCodec<R, W> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<R, W> Copy for TextCodec<R, W> {}
+
+fn typed_websocket_with_codec<T: WebSocketTransport, R: Protocol, W: Protocol>(sink: impl Sink<T::Message, Error = T::Error> + Unpin + Send, stream: impl Stream<Item = Result<T::Message, T::Error>> + Unpin + Send, config: WsConfig, codec: Option<TextCodec<R, W>>) -> (impl Sink<W, Error = WriteError> + Unpin, TypedWsStream<impl Stream<Item = Result<R, ReadError>> + Unpin>) {
+    #[cfg(feature = "keepalive")] let (sink, stream) = if let Some(keepalive) = config.keepalive {
+        let sink = Arc::new(Mutex::new(sink));
+        (
+            Either::Right(SharedSink::new(Arc::clone(&sink))),
+            Either::Right(self::keepalive::<T, _, _>(stream, sink, keepalive)),
+        )
+    } else {
+        (Either::Left(sink), Either::Left(stream.map_err(T::read_error)))
+    };
+    #[cfg(not(feature = "keepalive"))] let stream = stream.map_err(T::read_error);
+    let close_frame = Arc::<OnceLock<_>>::default();
+    (
+        sink.sink_map_err(|e| WriteError {
+            context: ErrorContext::WebSocketSink,
+            kind: T::write_error(e),
+        }).with_flat_map::<W, _, _>(move |msg| match codec.and_then(|codec| codec.encode).map_or_else(|| encode::<T>(&msg, config), |encode_text| encode_text_message::<T, W>(&msg, encode_text, config)) {
+            Ok(msgs) => Either::Left(stream::iter(msgs).map(Ok)),
+            Err(e) => Either::Right(stream::once(future::err(e))),
+        }),
+        TypedWsStream {
+            inner: Box::pin(stream::unfold((stream, Arc::clone(&close_frame)), move |(mut stream, close_frame)| async move {
+                loop {
+                    break match receive::<T, R, _>(&mut stream, config, codec.map(|codec| codec.decode), || ErrorContext::WebSocketStream).await {
+                        Ok(Received::Value(value)) => Some((Ok(value), (stream, close_frame))),
+                        Ok(Received::Closed(frame)) => {
+                            let _ = close_frame.set(frame.clone());
+                            // keep reading until the underlying stream ends, which happens once the WebSocket library has replied to the close message
+                            if is_clean_close(frame.as_ref()) { continue }
+                            Some((Err(ReadError {
+                                context: ErrorContext::WebSocketStream,
+                                kind: ReadErrorKind::WebSocketClosed(frame),
+                            }), (stream, close_frame)))
+                        }
+                        Ok(Received::EndOfStream) => None,
+                        Err(e) => Some((Err(e), (stream, close_frame))),
+                    }
+                }
+            })),
+            close_frame,
+        },
+    )
+}
+
+/// The stream half of a typed WebSocket, which yields the values received from the peer. Returned from [`typed_websocket`] and related functions.
+///
+/// If the peer closes the connection cleanly, i.e. with close code 1000 (normal closure), 1001 (going away), or no close code, the stream ends. Otherwise, it yields a [`ReadErrorKind::WebSocketClosed`] error first. Either way, the close code and reason are available from [`close_frame`](Self::close_frame) afterwards.
+pub struct TypedWsStream<St> {
+    inner: St,
+    close_frame: Arc<OnceLock<Option<WsCloseFrame>>>,
+}
+
+impl<St> TypedWsStream<St> {
+    /// Returns the close code and reason sent by the peer when it closed the connection.
+    ///
+    /// Returns `None` if the peer hasn't closed the connection (yet) or closed it without a close code.
+    pub fn close_frame(&self) -> Option<&WsCloseFrame> {
+        self.close_frame.get().and_then(Option::as_ref)
+    }
+}
+
+impl<R, St: Stream<Item = Result<R, ReadError>> + Unpin> Stream for TypedWsStream<St> {
+    type Item = Result<R, ReadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// The stream half of `TypedWebSocket` and `TypedChannel`, boxed so the wrapper types can be named.
+type BoxedTypedWsStream<R> = TypedWsStream<Pin<Box<dyn Stream<Item = Result<R, ReadError>> + Send>>>;
+
+/// An [`axum` WebSocket](axum::extract::ws::WebSocket) which sends values of type `W` and receives values of type `R`.
+///
+/// This uses the same representation of messages as functions like `websocket029`, including the chunking of large messages, so it can talk to clients using those. It implements [`Sink`] and [`Stream`] with the same behavior as the pair returned from [`typed_websocket`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// async fn handler(ws: WebSocketUpgrade) -> Response {
+///     ws.on_upgrade(|socket| async move {
+///         let mut socket = TypedWebSocket::<ClientMessage, ServerMessage>::new(socket);
+///         while let Some(Ok(msg)) = socket.next().await {
+///             // ...
+///         }
+///     })
+/// }
+/// ```
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+pub struct TypedWebSocket<R, W> {
+    sink: Pin<Box<dyn Sink<W, Error = WriteError> + Send>>,
+    stream: BoxedTypedWsStream<R>,
+}
+
+#[cfg(feature = "axum")]
+impl<R: Protocol + Send + 'static, W: Protocol + 'static> TypedWebSocket<R, W> {
+    /// Wraps the given WebSocket, using the [default](WsConfig::default) limits on message sizes.
+    pub fn new(websocket: axum_ws::WebSocket) -> Self {
+        Self::with_config(websocket, WsConfig::default())
+    }
+
+    /// Wraps the given WebSocket, with message sizes limited and large messages chunked as specified.
+    pub fn with_config(websocket: axum_ws::WebSocket, config: WsConfig) -> Self {
+        let (sink, stream) = websocket.split();
+        let (sink, stream) = typed_websocket::<Axum, R, W>(sink, stream, config);
+        Self {
+            sink: Box::pin(sink),
+            stream: TypedWsStream {
+                inner: Box::pin(stream.inner),
+                close_frame: stream.close_frame,
+            },
+        }
+    }
+
+    /// Returns the close code and reason sent by the peer when it closed the connection, see [`TypedWsStream::close_frame`].
+    pub fn close_frame(&self) -> Option<&WsCloseFrame> {
+        self.stream.close_frame()
+    }
+
+    /// Splits this WebSocket into its sink and stream halves, e.g. to send and receive concurrently.
+    pub fn split(self) -> (impl Sink<W, Error = WriteError> + Send, TypedWsStream<impl Stream<Item = Result<R, ReadError>> + Send>) {
+        (self.sink, self.stream)
+    }
+}
+
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+impl<R: Protocol + Send + 'static, W: Protocol + 'static> From<axum_ws::WebSocket> for TypedWebSocket<R, W> {
+    fn from(websocket: axum_ws::WebSocket) -> Self {
+        Self::new(websocket)
+    }
+}
+
+/// A typed wrapper around the stream of a [`rocket_ws`](https://docs.rs/rocket_ws) channel, sending values of type `W` and receiving values of type `R`.
+///
+/// This works with any combined stream and sink of WebSocket messages, such as the `rocket_ws::stream::DuplexStream` passed to a channel handler, so it doesn't depend on `rocket_ws` itself. The [`WebSocketTransport`] is chosen when wrapping the stream: `Tungstenite021` for the latest `rocket_ws` crates.io release, or `Tungstenite024` for the `master` branch of `rocket_ws` on GitHub.
+///
+/// This uses the same representation of messages as functions like `websocket021`, including the chunking of large messages. It implements [`Sink`] and [`Stream`] with the same behavior as the pair returned from [`typed_websocket`].
+///
+/// With the `tokio-tungstenite021` or `tokio-tungstenite024` feature flags, [`ReadError`] and [`WriteError`] convert into the corresponding `rocket_ws::result::Error`, so they can be propagated from a channel handler using `?`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[rocket::get("/ws")]
+/// fn ws(ws: rocket_ws::WebSocket) -> rocket_ws::Channel<'static> {
+///     ws.channel(|stream| Box::pin(async move {
+///         let mut channel = TypedChannel::<ClientMessage, ServerMessage>::new::<Tungstenite021>(stream);
+///         while let Some(msg) = channel.try_next().await? {
+///             // ...
+///         }
+///         Ok(())
+///     }))
+/// }
+/// ```
+pub struct TypedChannel<R, W> {
+    sink: Pin<Box<dyn Sink<W, Error = WriteError> + Send>>,
+    stream: BoxedTypedWsStream<R>,
+}
+
+impl<R: Protocol + Send + 'static, W: Protocol + 'static> TypedChannel<R, W> {
+    /// Wraps the given `rocket_ws::stream::DuplexStream`, using the [default](WsConfig::default) limits on message sizes.
+    pub fn new<T: WebSocketTransport + 'static>(stream: impl Stream<Item = Result<T::Message, T::Error>> + Sink<T::Message, Error = T::Error> + Send + Unpin + 'static) -> Self {
+        Self::with_config::<T>(stream, WsConfig::default())
+    }
+
+    /// Wraps the given `rocket_ws::stream::DuplexStream`, with message sizes limited and large messages chunked as specified.
+    pub fn with_config<T: WebSocketTransport + 'static>(stream: impl Stream<Item = Result<T::Message, T::Error>> + Sink<T::Message, Error = T::Error> + Send + Unpin + 'static, config: WsConfig) -> Self {
+        let (sink, stream) = stream.split();
+        let (sink, stream) = typed_websocket::<T, R, W>(sink, stream, config);
+        Self {
+            sink: Box::pin(sink),
+            stream: TypedWsStream {
+                inner: Box::pin(stream.inner),
+                close_frame: stream.close_frame,
+            },
+        }
+    }
+
+    /// Returns the close code and reason sent by the peer when it closed the connection, see [`TypedWsStream::close_frame`].
+    pub fn close_frame(&self) -> Option<&WsCloseFrame> {
+        self.stream.close_frame()
+    }
+
+    /// Splits this channel into its sink and stream halves, e.g. to send and receive concurrently.
+    pub fn split(self) -> (impl Sink<W, Error = WriteError> + Send, TypedWsStream<impl Stream<Item = Result<R, ReadError>> + Send>) {
+        (self.sink, self.stream)
+    }
+}
+
+macro_rules! impl_tungstenite_error_from {
+    ($feature:literal, $tungstenite:ident) => {
+        #[cfg(feature = $feature)]
+        #[cfg_attr(docsrs, doc(cfg(feature = $feature)))]
+        impl From<ReadError> for $tungstenite::Error {
+            fn from(e: ReadError) -> Self {
+                Self::Io(e.into())
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        #[cfg_attr(docsrs, doc(cfg(feature = $feature)))]
+        impl From<WriteError> for $tungstenite::Error {
+            fn from(e: WriteError) -> Self {
+                Self::Io(e.into())
+            }
+        }
+    };
+}
+
+impl_tungstenite_error_from!("tokio-tungstenite021", tungstenite021);
+impl_tungstenite_error_from!("tokio-tungstenite024", tungstenite024);
+
+macro_rules! impl_typed_duplex {
+    ($feature:literal, $ty:ident) => {
+        #[cfg(feature = $feature)]
+        impl<R, W> Stream for $ty<R, W> {
+            type Item = Result<R, ReadError>;
+
+            fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                self.stream.poll_next_unpin(cx)
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        impl<R, W> Sink<W> for $ty<R, W> {
+            type Error = WriteError;
+
+            fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WriteError>> {
+                self.sink.as_mut().poll_ready(cx)
+            }
+
+            fn start_send(mut self: Pin<&mut Self>, item: W) -> Result<(), WriteError> {
+                self.sink.as_mut().start_send(item)
+            }
+
+            fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WriteError>> {
+                self.sink.as_mut().poll_flush(cx)
+            }
+
+            fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), WriteError>> {
+                self.sink.as_mut().poll_close(cx)
+            }
+        }
+    };
+}
+
+impl_typed_duplex!("axum", TypedWebSocket);
+impl_typed_duplex!("websocket", TypedChannel);
+
+/// A blocking [`tungstenite029`] WebSocket which sends values of type `W` and receives values of type `R`.
+///
+/// This uses the same representation of messages as functions like `websocket029`, including the chunking of large messages, so it can talk to peers using those. It is returned from `connect_sync029` and `accept_sync029`, but can also wrap an existing WebSocket using [`new`](Self::new).
+///
+/// `Ping` and `Pong` messages are handled transparently. The [`Iterator`] implementation yields received values and ends when the peer closes the connection cleanly, i.e. with close code 1000 (normal closure), 1001 (going away), or no close code; otherwise, it yields a [`ReadErrorKind::WebSocketClosed`] error first. Either way, the close code and reason are available from [`close_frame`](Self::close_frame) afterwards.
+#[cfg(feature = "tokio-tungstenite029")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-tungstenite029")))]
+pub struct SyncWebSocket<R, W, S = tungstenite029::stream::MaybeTlsStream<std::net::TcpStream>> {
+    websocket: tungstenite029::WebSocket<S>,
+    config: WsConfig,
+    closed: bool,
+    close_frame: Option<WsCloseFrame>,
+    _marker: std::marker::PhantomData<fn(W) -> R>,
+}
+
+#[cfg(feature = "tokio-tungstenite029")]
+impl<R: Protocol, W: Protocol, S: io::Read + io::Write> SyncWebSocket<R, W, S> {
+    /// Wraps the given WebSocket, using the [default](WsConfig::default) limits on message sizes.
+    pub fn new(websocket: tungstenite029::WebSocket<S>) -> Self {
+        Self::with_config(websocket, WsConfig::default())
+    }
+
+    /// Wraps the given WebSocket, with message sizes limited and large messages chunked as specified.
+    pub fn with_config(websocket: tungstenite029::WebSocket<S>, config: WsConfig) -> Self {
+        Self {
+            closed: false,
+            close_frame: None,
+            _marker: std::marker::PhantomData,
+            websocket, config,
+        }
+    }
+
+    /// Sends a value and flushes the WebSocket.
+    pub fn send(&mut self, value: &W) -> Result<(), WriteError> {
+        value.write_ws_sync_transport::<Tungstenite029>(|msg| self.websocket.send(msg), self.config)
+    }
+
+    /// Blocks until a value is received.
+    ///
+    /// Unlike the [`Iterator`] implementation, this returns an error if the connection has been closed.
+    pub fn recv(&mut self) -> Result<R, ReadError> {
+        let res = R::read_ws_sync_transport::<Tungstenite029>(|| self.websocket.read(), self.config);
+        if let Err(ReadError { kind: ReadErrorKind::WebSocketClosed(frame), .. }) = &res {
+            self.close_frame.clone_from(frame);
+            // send the close reply queued by tungstenite
+            let _ = self.websocket.flush();
+        }
+        res
+    }
+
+    /// Returns the close code and reason sent by the peer when it closed the connection.
+    ///
+    /// Returns `None` if the peer hasn't closed the connection (yet) or closed it without a close code.
+    pub fn close_frame(&self) -> Option<&WsCloseFrame> {
+        self.close_frame.as_ref()
+    }
+
+    /// Starts the closing handshake. Values received afterwards can still be read until the peer confirms the close, at which point the [`Iterator`] implementation ends.
+    pub fn close(&mut self) -> Result<(), WriteError> {
+        self.websocket.close(None).and_then(|()| self.websocket.flush()).map_err(|e| WriteError {
+            context: ErrorContext::DefaultImpl,
+            kind: e.into(),
+        })
+    }
+
+    /// Returns a reference to the underlying WebSocket.
+    pub fn get_ref(&self) -> &tungstenite029::WebSocket<S> { &self.websocket }
+    /// Returns a mutable reference to the underlying WebSocket.
+    pub fn get_mut(&mut self) -> &mut tungstenite029::WebSocket<S> { &mut self.websocket }
+    /// Returns the underlying WebSocket.
+    pub fn into_inner(self) -> tungstenite029::WebSocket<S> { self.websocket }
+}
+
+#[cfg(feature = "tokio-tungstenite029")]
+impl<R: Protocol, W: Protocol, S: io::Read + io::Write> Iterator for SyncWebSocket<R, W, S> {
+    type Item = Result<R, ReadError>;
+
+    fn next(&mut self) -> Option<Result<R, ReadError>> {
+        if self.closed { return None }
+        match self.recv() {
+            Ok(value) => Some(Ok(value)),
+            Err(ReadError { kind: ReadErrorKind::WebSocketClosed(ref frame), .. }) if is_clean_close(frame.as_ref()) => {
+                self.closed = true;
+                None
+            }
+            Err(ReadError { kind: ReadErrorKind::Tungstenite029(tungstenite029::Error::ConnectionClosed | tungstenite029::Error::AlreadyClosed), .. }) => {
+                self.closed = true;
+                None
+            }
+            Err(e) => {
+                self.closed = matches!(e.kind, ReadErrorKind::WebSocketClosed(_) | ReadErrorKind::Tungstenite029(_));
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// The WebSocket subprotocol indicating the [binary format](WsFormat::Binary).
+#[cfg(feature = "websocket-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "websocket-json")))]
+pub const WS_SUBPROTOCOL_BINARY: &str = "async-proto";
+
+/// The WebSocket subprotocol indicating the [JSON format](WsFormat::Json).
+#[cfg(feature = "websocket-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "websocket-json")))]
+pub const WS_SUBPROTOCOL_JSON: &str = "async-proto.json";
+
+/// The format in which [`typed_websocket_json`] and related functions send values.
+///
+/// The format is negotiated during the WebSocket handshake using the subprotocols [`WS_SUBPROTOCOL_BINARY`] and [`WS_SUBPROTOCOL_JSON`]. Regardless of the format, values are accepted in both formats.
+///
+/// Functions which also perform the handshake (`websocket_json029` and `accept_websocket_json029`) are only provided for `tokio-tungstenite` 0.29. With other WebSocket libraries, including `axum`, use [`client_header`](Self::client_header) or [`negotiate`](Self::negotiate) to choose the subprotocol during the handshake, then pass the connection to [`typed_websocket_json`] along with the format.
+#[cfg(feature = "websocket-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "websocket-json")))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WsFormat {
+    /// Values are sent in binary messages using their [`Protocol`] implementation, the same as with [`typed_websocket`].
+    #[default]
+    Binary,
+    /// Values are sent in text messages containing a JSON rendering of them, using their `serde` implementations.
+    ///
+    /// This makes the messages readable in tools like browser devtools or `websocat`, and easier to produce for clients not written in Rust. Messages in this format are never chunked.
+    Json,
+}
+
+#[cfg(feature = "websocket-json")]
+impl WsFormat {
+    /// The subprotocol indicating this format.
+    pub fn subprotocol(self) -> &'static str {
+        match self {
+            Self::Binary => WS_SUBPROTOCOL_BINARY,
+            Self::Json => WS_SUBPROTOCOL_JSON,
+        }
+    }
+
+    /// The value of the `Sec-WebSocket-Protocol` header a client should send to request this format. The JSON format is requested with the binary format as a fallback.
+    pub fn client_header(self) -> &'static str {
+        match self {
+            Self::Binary => WS_SUBPROTOCOL_BINARY,
+            Self::Json => "async-proto.json, async-proto",
+        }
+    }
+
+    /// Returns the format indicated by the subprotocol selected by the server during the handshake. Anything other than [`WS_SUBPROTOCOL_JSON`] indicates the binary format.
+    pub fn from_subprotocol(subprotocol: Option<&str>) -> Self {
+        if subprotocol == Some(WS_SUBPROTOCOL_JSON) { Self::Json } else { Self::Binary }
+    }
+
+    /// Chooses a format based on the value of the `Sec-WebSocket-Protocol` header sent by a client, using the first supported subprotocol it lists.
+    ///
+    /// Returns `None` if the client didn't offer any of the subprotocols of this crate, in which case the server should not select a subprotocol and use the binary format.
+    pub fn negotiate(offered: &str) -> Option<Self> {
+        offered.split(',').find_map(|subprotocol| match subprotocol.trim() {
+            WS_SUBPROTOCOL_BINARY => Some(Self::Binary),
+            WS_SUBPROTOCOL_JSON => Some(Self::Json),
+            _ => None,
+        })
+    }
+}
+
+/// Same as [`typed_websocket`], but sends values in the given format and additionally accepts values in the [JSON format](WsFormat::Json).
+///
+/// The format should be the one negotiated during the handshake, see [`WsFormat`].
+#[cfg(feature = "websocket-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "websocket-json")))]
+pub fn typed_websocket_json<T: WebSocketTransport, R: Protocol + DeserializeOwned, W: Protocol + Serialize>(sink: impl Sink<T::Message, Error = T::Error> + Unpin + Send, stream: impl Stream<Item = Result<T::Message, T::Error>> + Unpin + Send, format: WsFormat, config: WsConfig) -> (impl Sink<W, Error = WriteError> + Unpin, TypedWsStream<impl Stream<Item = Result<R, ReadError>> + Unpin>) {
+    typed_websocket_with_codec::<T, R, W>(sink, stream, config, Some(TextCodec {
+        decode: |text| serde_json::from_str(text).map_err(ReadErrorKind::Json),
+        encode: match format {
+            WsFormat::Binary => None,
+            WsFormat::Json => Some(|value| serde_json::to_string(value).map_err(WriteErrorKind::Json)),
+        },
+    }))
+}
+
+/// Returns whether a `Close` message with the given frame ends a connection cleanly, i.e. whether it has no close code or the code for a normal closure or a peer going away.
+fn is_clean_close(frame: Option<&WsCloseFrame>) -> bool {
+    frame.is_none_or(|frame| matches!(frame.code, CLOSE_CODE_NORMAL | CLOSE_CODE_GOING_AWAY))
+}
+
+/// The sink half of a WebSocket, shared with [`keepalive`] so it can send pings on the same connection.
+#[cfg(feature = "keepalive")]
+struct SharedSink<Si> {
+    sink: Arc<Mutex<Si>>,
+    /// An attempt to lock the sink which is still pending. Kept between polls so the waker stays registered.
+    lock: Option<OwnedMutexLockFuture<Si>>,
+    /// The lock acquired by a successful `poll_ready`, held until the corresponding `start_send` so pings can't be sent in between.
+    guard: Option<OwnedMutexGuard<Si>>,
+}
+
+#[cfg(feature = "keepalive")]
+impl<Si> SharedSink<Si> {
+    fn new(sink: Arc<Mutex<Si>>) -> Self {
+        Self { sink, lock: None, guard: None }
+    }
+
+    fn poll_lock(&mut self, cx: &mut Context<'_>) -> Poll<OwnedMutexGuard<Si>> {
+        if let Some(guard) = self.guard.take() { return Poll::Ready(guard) }
+        let lock = self.lock.get_or_insert_with(|| Arc::clone(&self.sink).lock_owned());
+        let guard = ready!(lock.poll_unpin(cx));
+        self.lock = None;
+        Poll::Ready(guard)
+    }
+}
+
+#[cfg(feature = "keepalive")]
+impl<M, Si: Sink<M> + Unpin> Sink<M> for SharedSink<Si> {
+    type Error = Si::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        let mut guard = ready!(self.poll_lock(cx));
+        ready!(guard.poll_ready_unpin(cx))?;
+        self.guard = Some(guard);
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: M) -> Result<(), Si::Error> {
+        self.guard.take().expect("start_send called without poll_ready").start_send_unpin(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        ready!(self.poll_lock(cx)).poll_flush_unpin(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        ready!(self.poll_lock(cx)).poll_close_unpin(cx)
+    }
+}
+
+/// Sends a ping on the shared sink whenever nothing has been received from the stream half of a WebSocket for [`WsKeepalive::interval`].
+///
+/// If nothing is received for [`WsKeepalive::timeout`] after sending a ping, the stream yields a [`TimedOut`](io::ErrorKind::TimedOut) error and ends.
+#[cfg(feature = "keepalive")]
+fn keepalive<T: WebSocketTransport, St: Stream<Item = Result<T::Message, T::Error>> + Unpin, Si: Sink<T::Message, Error = T::Error> + Unpin>(stream: St, sink: Arc<Mutex<Si>>, config: WsKeepalive) -> Pin<Box<impl Stream<Item = Result<T::Message, ReadErrorKind>>>> {
+    Box::pin(stream::unfold(Some((stream, Instant::now() + config.interval, None)), move |state| {
+        let sink = Arc::clone(&sink);
+        async move {
+            let (mut stream, mut next_ping, mut deadline) = state?;
+            loop {
+                match timeout_at(deadline.map_or(next_ping, |deadline: Instant| deadline.min(next_ping)), stream.next()).await {
+                    Ok(Some(res)) => break Some((res.map_err(T::read_error), Some((stream, Instant::now() + config.interval, None)))),
+                    Ok(None) => break None,
+                    Err(_) if deadline.is_some_and(|deadline| Instant::now() >= deadline) => break Some((Err(io::Error::from(io::ErrorKind::TimedOut).into()), None)),
+                    Err(_) => if Instant::now() >= next_ping {
+                        if let Err(e) = sink.lock().await.send(T::ping_message()).await {
+                            break Some((Err(T::read_error(e)), None))
+                        }
+                        next_ping = Instant::now() + config.interval;
+                        deadline.get_or_insert(Instant::now() + config.timeout);
+                    },
+                }
+            }
+        }
+    }))
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "reconnect")]
+
+use {
+    std::time::Duration,
+    futures::{
+        SinkExt as _,
+        StreamExt as _,
+    },
+    tokio::{
+        net::TcpListener,
+        time::timeout,
+    },
+    async_proto::{
+        ReconnectConfig,
+        ReconnectEvent,
+        ReconnectingWebSocket,
+        accept_websocket029,
+    },
+};
+
+#[tokio::test]
+async fn reconnect_resends_buffered_values() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        // first connection: receive the on_connect value and one buffered value, then drop the connection without a close frame
+        let (tcp, _) = listener.accept().await.unwrap();
+        let (sink, mut stream) = accept_websocket029::<String, String>(tcp).await.unwrap();
+        let first = vec![stream.next().await.unwrap().unwrap(), stream.next().await.unwrap().unwrap()];
+        drop((sink, stream));
+        // second connection: the on_connect value is sent again, followed by the value sent while disconnected
+        let (tcp, _) = listener.accept().await.unwrap();
+        let (mut sink, mut stream) = accept_websocket029::<String, String>(tcp).await.unwrap();
+        let second = vec![stream.next().await.unwrap().unwrap(), stream.next().await.unwrap().unwrap()];
+        sink.send("welcome".to_owned()).await.unwrap();
+        // keep the connection open until the client shuts down
+        assert!(stream.next().await.is_none_or(|res| res.is_err()));
+        (first, second)
+    });
+    let config = ReconnectConfig {
+        initial_backoff: Duration::from_millis(100),
+        ..ReconnectConfig::default()
+    };
+    let mut client = ReconnectingWebSocket::<String, String>::new(url, config, || vec!["hello".to_owned()]);
+    client.send("a".to_owned());
+    timeout(Duration::from_secs(10), async {
+        assert!(matches!(client.next().await, Some(ReconnectEvent::Connected)));
+        match client.next().await {
+            Some(ReconnectEvent::Disconnected { error: Some(_), retry_in }) => assert_eq!(retry_in, Duration::from_millis(100)),
+            event => panic!("expected Disconnected event, got {event:?}"),
+        }
+        client.send("b".to_owned());
+        assert!(matches!(client.next().await, Some(ReconnectEvent::Connected)));
+        match client.next().await {
+            Some(ReconnectEvent::Message(msg)) => assert_eq!(msg, "welcome"),
+            event => panic!("expected Message event, got {event:?}"),
+        }
+    }).await.unwrap();
+    drop(client);
+    let (first, second) = timeout(Duration::from_secs(10), server).await.unwrap().unwrap();
+    assert_eq!(first, ["hello", "a"]);
+    assert_eq!(second, ["hello", "b"]);
+}